@@ -0,0 +1,21 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rs_json_parser::{parse_json, parse_json_compact};
+
+fn compact_input() -> String {
+    let record = r#"{"name":"John Doe","age":30,"is_student":false,"marks":[90.0,-80.0,85.1],"address":{"city":"New York","zip":10001}}"#;
+    format!("[{}]", vec![record; 1000].join(","))
+}
+
+fn bench_compact(c: &mut Criterion) {
+    let input = compact_input();
+
+    c.bench_function("parse_json compact input", |b| {
+        b.iter(|| parse_json(&mut black_box(input.as_str())).unwrap())
+    });
+    c.bench_function("parse_json_compact compact input", |b| {
+        b.iter(|| parse_json_compact(&mut black_box(input.as_str())).unwrap())
+    });
+}
+
+criterion_group!(benches, bench_compact);
+criterion_main!(benches);
@@ -0,0 +1,21 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rs_json_parser::{parse_f64_list, parse_json};
+
+fn numbers_input() -> String {
+    let numbers: Vec<String> = (0..10_000).map(|i| format!("{}.{}e-{}", i, i % 97, i % 7)).collect();
+    format!("[{}]", numbers.join(", "))
+}
+
+fn bench_f64_list(c: &mut Criterion) {
+    let input = numbers_input();
+
+    c.bench_function("parse_json number array", |b| {
+        b.iter(|| parse_json(&mut black_box(input.as_str())).unwrap())
+    });
+    c.bench_function("parse_f64_list number array", |b| {
+        b.iter(|| parse_f64_list(black_box(input.as_str())).unwrap())
+    });
+}
+
+criterion_group!(benches, bench_f64_list);
+criterion_main!(benches);
@@ -0,0 +1,61 @@
+//! Compares the number of allocations made re-parsing similar documents with `parse_json`
+//! against `parse_into` reusing the same buffer.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use rs_json_parser::{parse_into, parse_json, JsonValue};
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn measure(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    f();
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+fn main() {
+    let inputs: Vec<String> = (0..100)
+        .map(|i| {
+            let record = format!(r#"{{"id": {}, "name": "user {}", "tags": ["alpha", "beta"], "address": {{"city": "New York"}}}}"#, i, i);
+            format!("[{}]", vec![record; 100].join(","))
+        })
+        .collect();
+
+    let fresh = measure(|| {
+        for input in &inputs {
+            drop(parse_json(&mut input.as_str()).unwrap());
+        }
+    });
+
+    let mut buffer = JsonValue::Null;
+    parse_into(&inputs[0], &mut buffer).unwrap();
+    let reused = measure(|| {
+        for input in &inputs {
+            parse_into(input, &mut buffer).unwrap();
+        }
+    });
+
+    println!("100 parses with parse_json:             {} allocations", fresh);
+    println!("100 parses with parse_into, one buffer: {} allocations", reused);
+}
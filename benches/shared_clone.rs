@@ -0,0 +1,44 @@
+//! Compares the heap usage of 100 deep clones against 100 shared clones.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use rs_json_parser::parse_json;
+
+struct CountingAlloc;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn measure<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    let ret = f();
+    (ret, ALLOCATED.load(Ordering::Relaxed) - before)
+}
+
+fn main() {
+    let record = r#"{"name": "John Doe", "city": "New York", "tags": ["alpha", "beta", "gamma"], "bio": "a fairly long free-form description string"}"#;
+    let input = format!("[{}]", vec![record; 100].join(","));
+    let json = parse_json(&mut input.as_str()).unwrap();
+
+    let (deep, deep_bytes) = measure(|| (0..100).map(|_| json.clone()).collect::<Vec<_>>());
+    let shared = json.shared_clone();
+    let (fanned, shared_bytes) = measure(|| (0..100).map(|_| shared.clone()).collect::<Vec<_>>());
+
+    println!("100 deep clones:   {} bytes", deep_bytes);
+    println!("100 shared clones: {} bytes", shared_bytes);
+    drop((deep, fanned));
+}
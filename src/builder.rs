@@ -0,0 +1,75 @@
+use crate::{JsonValue, Map};
+use crate::prelude::*;
+
+/// Builds a `JsonValue::Object` one key at a time.
+#[derive(Debug, Clone, Default)]
+pub struct ObjectBuilder {
+    map: Map<String, JsonValue>,
+}
+
+impl ObjectBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn key(mut self, key: impl Into<String>, value: JsonValue) -> Self {
+        self.map.insert(key.into(), value);
+        self
+    }
+
+    pub fn build(self) -> JsonValue {
+        JsonValue::Object(self.map)
+    }
+}
+
+/// Builds a `JsonValue::Array` one element at a time.
+#[derive(Debug, Clone, Default)]
+pub struct ArrayBuilder {
+    values: Vec<JsonValue>,
+}
+
+impl ArrayBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(mut self, value: JsonValue) -> Self {
+        self.values.push(value);
+        self
+    }
+
+    pub fn build(self) -> JsonValue {
+        JsonValue::Array(self.values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_json;
+
+    #[test]
+    fn object_builder_should_work() {
+        let input = r#"{"name": "John Doe", "age": 30, "is_student": false}"#;
+        let expected = parse_json(&mut (&*input)).unwrap();
+
+        let ret = ObjectBuilder::new()
+            .key("name", JsonValue::String("John Doe".to_string()))
+            .key("age", JsonValue::Number(30.0))
+            .key("is_student", JsonValue::Boolean(false))
+            .build();
+        assert_eq!(ret, expected);
+    }
+
+    #[test]
+    fn array_builder_should_work() {
+        let input = "[1, null, true]";
+        let expected = parse_json(&mut (&*input)).unwrap();
+
+        let mut builder = ArrayBuilder::new();
+        for v in [JsonValue::Number(1.0), JsonValue::Null, JsonValue::Boolean(true)] {
+            builder = builder.push(v);
+        }
+        assert_eq!(builder.build(), expected);
+    }
+}
@@ -0,0 +1,290 @@
+use core::cell::RefCell;
+use anyhow::Result;
+use winnow::Parser;
+use winnow::combinator::{delimited, eof, terminated};
+use winnow::error::{StrContext, StrContextValue};
+use crate::options::ParseState;
+use crate::ser::write_string;
+use crate::{expect_value, syntax_error, ws, Input, JsonValue, Map, ParserOptions, PrettyConfig};
+use crate::prelude::*;
+
+/// The comments around one value, as written in the source including their `//` or `/* */`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValueComments {
+    /// Comments on the lines before the value, or before its key.
+    pub leading: Vec<String>,
+    /// Comments after the value on the same line, including after its `,`.
+    pub trailing: Vec<String>,
+    /// For containers, comments on their own lines after the last element or member, or in an
+    /// empty container.
+    pub dangling: Vec<String>,
+}
+
+/// The comments of a JSONC document, see `parse_json_with_comments`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Comments {
+    /// Keyed by the JSON Pointer of the value they belong to; values without comments are absent.
+    pub values: Map<String, ValueComments>,
+    /// Comments on their own lines after the top-level value.
+    pub end: Vec<String>,
+}
+
+/// Attributes comments to values as `parse_value` goes.
+///
+/// A comment on the same line as the value just completed trails it; any other comment waits
+/// in `pending` for the next value to start, or for the enclosing container to close.
+#[derive(Debug, Default)]
+pub(crate) struct CommentCollector {
+    comments: Comments,
+    pending: Vec<String>,
+    /// The pointer of the value just completed, until a newline, key or value follows it.
+    last_value: Option<String>,
+}
+
+impl CommentCollector {
+    pub(crate) fn comment(&mut self, text: &str) {
+        let text = text.trim_end().to_string();
+        match &self.last_value {
+            Some(pointer) => self.comments.values.entry(pointer.clone()).or_default().trailing.push(text),
+            None => self.pending.push(text),
+        }
+    }
+
+    /// Comments after a newline no longer share a line with the value before them.
+    pub(crate) fn newline(&mut self) {
+        self.last_value = None;
+    }
+
+    pub(crate) fn key_start(&mut self) {
+        self.last_value = None;
+    }
+
+    pub(crate) fn value_start(&mut self, pointer: String) {
+        self.last_value = None;
+        if !self.pending.is_empty() {
+            self.comments.values.entry(pointer).or_default().leading.append(&mut self.pending);
+        }
+    }
+
+    pub(crate) fn value_end(&mut self, pointer: String) {
+        self.last_value = Some(pointer);
+    }
+
+    pub(crate) fn container_end(&mut self, pointer: String) {
+        if !self.pending.is_empty() {
+            self.comments.values.entry(pointer).or_default().dangling.append(&mut self.pending);
+        }
+    }
+
+    fn finish(mut self) -> Comments {
+        self.comments.end = self.pending;
+        self.comments
+    }
+}
+
+/// Parses JSONC, JSON with `//` and `/* */` comments, keeping the comments alongside the value
+/// so that `to_pretty_string_with_comments` can write them back, e.g. in a config editor.
+pub fn parse_json_with_comments(input: &str) -> Result<(JsonValue, Comments)> {
+    let options = ParserOptions::new().allow_comments(true);
+    let mut state = ParseState::new(&options);
+    state.comments = Some(RefCell::new(CommentCollector::default()));
+    let mut stateful = Input { input, state: &state };
+
+    let end = eof.context(StrContext::Expected(StrContextValue::Description("end of input")));
+    let value = terminated(delimited(ws, expect_value, ws), end)
+        .parse_next(&mut stateful)
+        .map_err(|e| syntax_error(input, &stateful, e))?;
+
+    let comments = state.comments.take().unwrap_or_default().into_inner().finish();
+    Ok((value, comments))
+}
+
+/// What `parse_json_with_trivia_stats` skipped between tokens.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TriviaStats {
+    pub comments: usize,
+    /// Whitespace outside strings and comments.
+    pub whitespace_bytes: usize,
+}
+
+/// Parses JSONC like `parse_json_with_comments`, but only counts the comments and whitespace
+/// it skips, e.g. for a formatter to judge how much reformatting would change.
+pub fn parse_json_with_trivia_stats(input: &str) -> Result<(JsonValue, TriviaStats)> {
+    let options = ParserOptions::new().allow_comments(true);
+    let mut state = ParseState::new(&options);
+    state.trivia = Some(RefCell::new(TriviaStats::default()));
+    let mut stateful = Input { input, state: &state };
+
+    let end = eof.context(StrContext::Expected(StrContextValue::Description("end of input")));
+    let value = terminated(delimited(ws, expect_value, ws), end)
+        .parse_next(&mut stateful)
+        .map_err(|e| syntax_error(input, &stateful, e))?;
+
+    Ok((value, state.trivia.take().unwrap_or_default().into_inner()))
+}
+
+impl JsonValue {
+    /// Writes the value as indented JSONC with `comments` put back in place, the inverse of
+    /// `parse_json_with_comments`.
+    ///
+    /// Containers are always expanded, so `config`'s inlining options don't apply, and object
+    /// members are written in map order rather than source order.
+    pub fn to_pretty_string_with_comments(&self, comments: &Comments, config: &PrettyConfig) -> String {
+        let none = ValueComments::default();
+        let root = comments.values.get("").unwrap_or(&none);
+
+        let mut out = String::new();
+        for comment in &root.leading {
+            out.push_str(comment);
+            out.push('\n');
+        }
+        write_commented(&mut out, self, &mut String::new(), comments, config, 0);
+        write_trailing(&mut out, &root.trailing);
+        for comment in &comments.end {
+            out.push('\n');
+            out.push_str(comment);
+        }
+        out
+    }
+}
+
+/// Writes `value`, found at `pointer`, starting on a line already indented to `depth`.
+fn write_commented(
+    out: &mut String,
+    value: &JsonValue,
+    pointer: &mut String,
+    comments: &Comments,
+    config: &PrettyConfig,
+    depth: usize,
+) {
+    let none = ValueComments::default();
+    let dangling = &comments.values.get(pointer.as_str()).unwrap_or(&none).dangling;
+    let (open, close, members): (char, char, Vec<(Option<&String>, &JsonValue)>) = match value {
+        JsonValue::Array(arr) => ('[', ']', arr.iter().map(|v| (None, v)).collect()),
+        JsonValue::Object(map) => ('{', '}', map.iter().map(|(k, v)| (Some(k), v)).collect()),
+        _ => {
+            out.push_str(&value.to_string());
+            return;
+        }
+    };
+    if members.is_empty() && dangling.is_empty() {
+        out.push(open);
+        out.push(close);
+        return;
+    }
+
+    out.push(open);
+    let len = pointer.len();
+    let count = members.len();
+    for (i, (key, v)) in members.into_iter().enumerate() {
+        pointer.push('/');
+        match key {
+            Some(key) => pointer.push_str(&crate::pointer::escape(key)),
+            None => pointer.push_str(&i.to_string()),
+        }
+        let own = comments.values.get(pointer.as_str()).unwrap_or(&none);
+
+        for comment in &own.leading {
+            newline(out, config, depth + 1);
+            out.push_str(comment);
+        }
+        newline(out, config, depth + 1);
+        if let Some(key) = key {
+            write_string(out, key).expect("writing to a String can't fail");
+            out.push_str(if config.space_after_colon { ": " } else { ":" });
+        }
+        write_commented(out, v, pointer, comments, config, depth + 1);
+        if i + 1 < count {
+            out.push(',');
+        }
+        write_trailing(out, &own.trailing);
+        pointer.truncate(len);
+    }
+    for comment in dangling {
+        newline(out, config, depth + 1);
+        out.push_str(comment);
+    }
+    newline(out, config, depth);
+    out.push(close);
+}
+
+/// Same-line comments; only the last can be a `//` comment, since it runs to the end of the line.
+fn write_trailing(out: &mut String, trailing: &[String]) {
+    for comment in trailing {
+        out.push(' ');
+        out.push_str(comment);
+    }
+}
+
+fn newline(out: &mut String, config: &PrettyConfig, depth: usize) {
+    out.push('\n');
+    out.push_str(&config.indent.repeat(depth));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_json;
+
+    const CONFIG: &str = r#"// app config
+{
+    /* the port */ "port": 8080, // http
+    "hosts": [
+        "a", /* primary */
+        // secondary
+        "b"
+        // more to come
+    ],
+    "empty": { /* nothing yet */ }
+} // done
+// eof
+"#;
+
+    #[test]
+    fn parse_json_with_comments_should_work() {
+        let (value, comments) = parse_json_with_comments(CONFIG).unwrap();
+        assert_eq!(value, parse_json(&mut r#"{"port": 8080, "hosts": ["a", "b"], "empty": {}}"#).unwrap());
+
+        let get = |pointer: &str| comments.values[pointer].clone();
+        assert_eq!(get("").leading, ["// app config"]);
+        assert_eq!(get("").trailing, ["// done"]);
+        assert_eq!(comments.end, ["// eof"]);
+        assert_eq!(get("/port").leading, ["/* the port */"]);
+        assert_eq!(get("/port").trailing, ["// http"]);
+        assert_eq!(get("/hosts/0").trailing, ["/* primary */"]);
+        assert_eq!(get("/hosts/1").leading, ["// secondary"]);
+        assert_eq!(get("/hosts").dangling, ["// more to come"]);
+        assert_eq!(get("/empty").dangling, ["/* nothing yet */"]);
+        assert!(!comments.values.contains_key("/hosts/0/x"));
+
+        assert!(parse_json(&mut &*CONFIG).is_err());
+        let options = ParserOptions::new().allow_comments(true);
+        assert_eq!(crate::parse_json_with_options(&mut &*CONFIG, &options).unwrap(), value);
+        assert!(parse_json_with_comments("[1 /* open").is_err());
+    }
+
+    #[test]
+    fn to_pretty_string_with_comments_should_round_trip() {
+        let (value, comments) = parse_json_with_comments(CONFIG).unwrap();
+        let output = value.to_pretty_string_with_comments(&comments, &PrettyConfig::default());
+        assert!(output.starts_with("// app config\n{"));
+        assert!(output.contains("\n  \"port\": 8080"));
+        assert!(output.contains("\n  \"empty\": {\n    /* nothing yet */\n  }"));
+        assert!(output.ends_with("} // done\n// eof"));
+
+        let (reparsed, recomments) = parse_json_with_comments(&output).unwrap();
+        assert_eq!(reparsed, value);
+        assert_eq!(recomments, comments);
+    }
+
+    #[test]
+    fn parse_json_with_trivia_stats_should_work() {
+        let input = "// config\n{\n        \"a b\": [1, 2], /* two */\n        \"c\":\t{}\n}\n";
+        let (value, stats) = parse_json_with_trivia_stats(input).unwrap();
+        assert_eq!(value, parse_json(&mut r#"{"a b": [1, 2], "c": {}}"#).unwrap());
+        assert_eq!(stats, TriviaStats { comments: 2, whitespace_bytes: 25 });
+
+        let (_, stats) = parse_json_with_trivia_stats("[]").unwrap();
+        assert_eq!(stats, TriviaStats::default());
+    }
+}
@@ -0,0 +1,148 @@
+use crate::{JsonValue, Map};
+use crate::prelude::*;
+
+impl From<bool> for JsonValue {
+    fn from(b: bool) -> Self {
+        JsonValue::Boolean(b)
+    }
+}
+
+impl From<f64> for JsonValue {
+    fn from(n: f64) -> Self {
+        JsonValue::Number(n)
+    }
+}
+
+macro_rules! from_integer {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for JsonValue {
+                fn from(n: $t) -> Self {
+                    JsonValue::Integer(n.into())
+                }
+            }
+        )*
+    };
+}
+
+from_integer!(i32, i64, u32);
+
+/// Kept exact as an `Integer` up to `i64::MAX`; larger values become a rounded `Number`.
+impl From<u64> for JsonValue {
+    fn from(n: u64) -> Self {
+        i64::try_from(n).map_or(JsonValue::Number(n as f64), JsonValue::Integer)
+    }
+}
+
+impl From<String> for JsonValue {
+    fn from(s: String) -> Self {
+        JsonValue::String(s)
+    }
+}
+
+impl From<&str> for JsonValue {
+    fn from(s: &str) -> Self {
+        JsonValue::String(s.to_string())
+    }
+}
+
+impl<V: Into<JsonValue>> From<Vec<V>> for JsonValue {
+    fn from(values: Vec<V>) -> Self {
+        JsonValue::Array(values.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<V: Into<JsonValue>> From<Map<String, V>> for JsonValue {
+    fn from(map: Map<String, V>) -> Self {
+        JsonValue::Object(map.into_iter().map(|(k, v)| (k, v.into())).collect())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl JsonValue {
+    /// Whether the value is structurally equal to `other`, without converting either side.
+    /// Numbers, including `RawNumber`s, compare by value as `f64`.
+    pub fn eq_serde(&self, other: &serde_json::Value) -> bool {
+        use serde_json::Value;
+
+        match (self, other) {
+            (JsonValue::Null, Value::Null) => true,
+            (JsonValue::Boolean(a), Value::Bool(b)) => a == b,
+            (JsonValue::Integer(a), Value::Number(n)) if n.is_i64() => n.as_i64() == Some(*a),
+            (JsonValue::Number(_) | JsonValue::Integer(_) | JsonValue::RawNumber(_), Value::Number(n)) => {
+                self.as_f64().is_some_and(|a| n.as_f64() == Some(a))
+            }
+            (JsonValue::String(a), Value::String(b)) => a == b,
+            (JsonValue::Array(a), Value::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.eq_serde(b))
+            }
+            (JsonValue::Object(a), Value::Object(b)) => {
+                a.len() == b.len() && a.iter().all(|(k, v)| b.get(k).is_some_and(|w| v.eq_serde(w)))
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_json, parse_json_with_options, NumberMode, ParserOptions};
+
+    #[test]
+    fn from_integer_should_stay_exact() {
+        let options = ParserOptions::new().number_mode(NumberMode::IntFloat);
+        let above_f64 = (1_i64 << 53) + 1;
+        assert_eq!(JsonValue::from(above_f64), JsonValue::Integer(above_f64));
+        assert_eq!(JsonValue::from(i64::MAX).to_string(), i64::MAX.to_string());
+        assert_eq!(JsonValue::from(i64::MAX), parse_json_with_options(&mut &*i64::MAX.to_string(), &options).unwrap());
+
+        assert_eq!(JsonValue::from(i64::MAX as u64), JsonValue::Integer(i64::MAX));
+        assert_eq!(JsonValue::from(u64::MAX), JsonValue::Number(u64::MAX as f64));
+        assert_eq!(JsonValue::from(u32::MAX), JsonValue::Integer(u32::MAX.into()));
+    }
+
+    #[test]
+    fn from_vec_should_work() {
+        let options = ParserOptions::new().number_mode(NumberMode::IntFloat);
+        let json = JsonValue::from(vec![1_i64, -2, 3]);
+        assert_eq!(json, parse_json_with_options(&mut "[1, -2, 3]", &options).unwrap());
+
+        let nested: JsonValue = vec![vec!["a"], vec![]].into();
+        assert_eq!(nested, parse_json(&mut r#"[["a"], []]"#).unwrap());
+    }
+
+    #[test]
+    fn from_hash_map_should_work() {
+        let flags = Map::from([("debug".to_string(), true), ("tls".to_string(), false)]);
+        let json = JsonValue::from(flags);
+        assert_eq!(json, parse_json(&mut r#"{"debug": true, "tls": false}"#).unwrap());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn eq_serde_should_work() {
+        let json = crate::tests::sample();
+        let mut expected = serde_json::json!({
+            "name": "John Doe",
+            "age": 30,
+            "is_student": false,
+            "marks": [90.0, -80.0, 85.1],
+            "address": {"city": "New York", "zip": 10001},
+            "nested": {
+                "different_element_array": [1, null, true, "hello", {"a": 1, "s": "str"}],
+                "empty_arr": [],
+                "empty_obj": {}
+            },
+            "small_number": 0.00000000000005,
+            "scientific_number": -1.1e-30,
+            "scientific_number2": -1.1e+1
+        });
+        assert!(json.eq_serde(&expected));
+
+        expected["address"]["zip"] = serde_json::json!("10001");
+        assert!(!json.eq_serde(&expected));
+        assert!(!JsonValue::Array(vec![]).eq_serde(&serde_json::json!({})));
+        assert!(JsonValue::RawNumber("1.50".to_string()).eq_serde(&serde_json::json!(1.5)));
+    }
+}
@@ -0,0 +1,30 @@
+use chrono::{DateTime, Utc};
+use crate::JsonValue;
+
+impl JsonValue {
+    /// Interprets an RFC 3339 string value as a UTC timestamp.
+    ///
+    /// Parsing itself stays string-based; only this accessor looks at the contents.
+    pub fn as_datetime(&self) -> Option<DateTime<Utc>> {
+        let s = self.as_str()?;
+        DateTime::parse_from_rfc3339(s).ok().map(|dt| dt.with_timezone(&Utc))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn as_datetime_should_work() {
+        let json = JsonValue::String("2020-01-02T03:04:05Z".to_string());
+        assert_eq!(json.as_datetime(), Some(Utc.with_ymd_and_hms(2020, 1, 2, 3, 4, 5).unwrap()));
+
+        let json = JsonValue::String("2020-01-02T04:04:05+01:00".to_string());
+        assert_eq!(json.as_datetime(), Some(Utc.with_ymd_and_hms(2020, 1, 2, 3, 4, 5).unwrap()));
+
+        assert_eq!(JsonValue::String("yesterday".to_string()).as_datetime(), None);
+        assert_eq!(JsonValue::Number(1.0).as_datetime(), None);
+    }
+}
@@ -0,0 +1,200 @@
+use core::fmt::{self, Display, Formatter};
+use crate::JsonKind;
+use crate::prelude::*;
+
+/// Errors raised while parsing, or when querying or editing an already-parsed `JsonValue`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonError {
+    /// The input isn't valid JSON; `offset` is in bytes, `line` and `column` are 1-based.
+    /// `pointer` is the JSON Pointer of the innermost array or object open at the failure, if any.
+    Syntax {
+        message: String,
+        offset: usize,
+        line: usize,
+        column: usize,
+        pointer: Option<String>,
+    },
+    /// The input is longer than `ParserOptions::max_input_bytes`.
+    InputTooLarge {
+        len: usize,
+        max: usize,
+    },
+    /// The parse gave up after `ParserOptions::max_steps` steps.
+    Timeout {
+        max_steps: usize,
+    },
+    /// The pointer is neither empty nor starts with `/`.
+    InvalidPointer(String),
+    /// A key along the pointer doesn't exist.
+    PathNotFound(String),
+    /// An array index along the pointer is malformed or past the end.
+    IndexOutOfRange(String),
+    /// The pointer tries to descend into a scalar.
+    NotAContainer(String),
+    /// A JSON Patch document or one of its operations is malformed.
+    InvalidPatch(String),
+    /// A JSON Patch `test` operation didn't match the value at this pointer.
+    TestFailed(String),
+    /// An object has a key outside the allowed set; holds the pointer to that key's value.
+    UnknownKey(String),
+    /// An object lacks required keys; holds every missing key, in the order they were required.
+    MissingKeys(Vec<String>),
+    /// A number is outside the allowed range; holds the pointer to it.
+    OutOfRange(String),
+    /// A value isn't of the kind the caller asked for.
+    TypeMismatch {
+        expected: JsonKind,
+        actual: JsonKind,
+    },
+}
+
+impl JsonError {
+    pub(crate) fn syntax(source: &str, offset: usize, message: impl Into<String>) -> Self {
+        let (line, column) = line_column(source, offset);
+        JsonError::Syntax {
+            message: message.into(),
+            offset,
+            line,
+            column,
+            pointer: None,
+        }
+    }
+
+    /// Sets the pointer of a `Syntax` error to the container it occurred in.
+    pub(crate) fn in_container(mut self, container: Option<String>) -> Self {
+        if let JsonError::Syntax { pointer, .. } = &mut self {
+            *pointer = container;
+        }
+        self
+    }
+
+    /// The JSON Pointer the error refers to: for errors raised on a parsed value, the value
+    /// concerned, and for syntax errors the container the parse failed in.
+    pub fn pointer(&self) -> Option<&str> {
+        match self {
+            JsonError::Syntax { pointer, .. } => pointer.as_deref(),
+            JsonError::InputTooLarge { .. }
+            | JsonError::Timeout { .. }
+            | JsonError::InvalidPatch(_)
+            | JsonError::MissingKeys(_)
+            | JsonError::TypeMismatch { .. } => None,
+            JsonError::InvalidPointer(p)
+            | JsonError::PathNotFound(p)
+            | JsonError::IndexOutOfRange(p)
+            | JsonError::NotAContainer(p)
+            | JsonError::TestFailed(p)
+            | JsonError::UnknownKey(p)
+            | JsonError::OutOfRange(p) => Some(p),
+        }
+    }
+}
+
+/// The 1-based line and column of a byte offset into `source`.
+pub(crate) fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let consumed = &source[..offset];
+    let line = consumed.matches('\n').count() + 1;
+    let column = consumed.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+    (line, column)
+}
+
+impl Display for JsonError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonError::Syntax { message, line, column, .. } => {
+                write!(f, "Failed to parse JSON at line {}, column {}: {}", line, column, message)
+            }
+            JsonError::InputTooLarge { len, max } => {
+                write!(f, "Failed to parse JSON: input is {} bytes, more than the limit of {}", len, max)
+            }
+            JsonError::Timeout { max_steps } => {
+                write!(f, "Failed to parse JSON: gave up after exceeding the budget of {} steps", max_steps)
+            }
+            JsonError::InvalidPointer(p) => write!(f, "invalid JSON pointer `{}`", p),
+            JsonError::PathNotFound(p) => write!(f, "no value at `{}`", p),
+            JsonError::IndexOutOfRange(p) => write!(f, "array index out of range at `{}`", p),
+            JsonError::NotAContainer(p) => write!(f, "value at `{}` is not an array or object", p),
+            JsonError::InvalidPatch(msg) => write!(f, "invalid JSON patch: {}", msg),
+            JsonError::TestFailed(p) => write!(f, "patch test failed at `{}`", p),
+            JsonError::UnknownKey(p) => write!(f, "unknown key at `{}`", p),
+            JsonError::MissingKeys(keys) => write!(f, "missing keys `{}`", keys.join("`, `")),
+            JsonError::OutOfRange(p) => write!(f, "number out of range at `{}`", p),
+            JsonError::TypeMismatch { expected, actual } => write!(f, "expected {}, found {}", expected, actual),
+        }
+    }
+}
+
+impl core::error::Error for JsonError {}
+
+/// Serializes as a flat diagnostic: `message`, `line`, `column`, `offset` and `pointer`, with
+/// `null` for the fields that don't apply to the error.
+#[cfg(feature = "serde")]
+impl serde::Serialize for JsonError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let (line, column, offset) = match self {
+            JsonError::Syntax { line, column, offset, .. } => (Some(*line), Some(*column), Some(*offset)),
+            _ => (None, None, None),
+        };
+        let message = match self {
+            JsonError::Syntax { message, .. } => message.clone(),
+            _ => self.to_string(),
+        };
+
+        let mut diagnostic = serializer.serialize_struct("JsonError", 5)?;
+        diagnostic.serialize_field("message", &message)?;
+        diagnostic.serialize_field("line", &line)?;
+        diagnostic.serialize_field("column", &column)?;
+        diagnostic.serialize_field("offset", &offset)?;
+        diagnostic.serialize_field("pointer", &self.pointer())?;
+        diagnostic.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_json;
+
+    #[test]
+    fn syntax_error_should_locate() {
+        let input = "[1,\n  2,\n  x]";
+        let err = parse_json(&mut (&*input)).unwrap_err();
+        assert!(matches!(err.downcast::<JsonError>(), Ok(JsonError::Syntax { .. })));
+
+        let err = JsonError::syntax(input, 11, "unexpected `x`");
+        assert_eq!(err.to_string(), "Failed to parse JSON at line 3, column 3: unexpected `x`");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn error_should_serialize() {
+        let err = parse_json(&mut "{\n  \"a\" 1}").unwrap_err().downcast::<JsonError>().unwrap();
+        assert_eq!(
+            serde_json::to_value(&err).unwrap(),
+            serde_json::json!({"message": "expected `:`", "line": 2, "column": 7, "offset": 8, "pointer": ""})
+        );
+
+        let err = parse_json(&mut r#"{"a": [1, {"b": }]}"#).unwrap_err().downcast::<JsonError>().unwrap();
+        assert_eq!(serde_json::to_value(&err).unwrap()["pointer"], "/a/1");
+
+        let err = crate::parse_bytes(b"[\xFF]").unwrap_err().downcast::<JsonError>().unwrap();
+        assert_eq!(
+            serde_json::to_value(&err).unwrap(),
+            serde_json::json!({"message": "invalid UTF-8", "line": 1, "column": 2, "offset": 1, "pointer": null})
+        );
+
+        let mut json = crate::tests::sample();
+        let err = json.set("/address/street/name", crate::JsonValue::Null).unwrap_err();
+        assert_eq!(
+            serde_json::to_value(&err).unwrap(),
+            serde_json::json!({
+                "message": "no value at `/address/street/name`",
+                "line": null,
+                "column": null,
+                "offset": null,
+                "pointer": "/address/street/name"
+            })
+        );
+    }
+}
@@ -0,0 +1,62 @@
+use core::fmt::{self, Display, Formatter};
+use core::ops::Deref;
+use crate::JsonValue;
+
+/// A `JsonValue` that can no longer be changed, made with `JsonValue::freeze`.
+///
+/// It derefs to `&JsonValue`, so every lookup (`pointer`, `as_object`, `get_str`, iterators over
+/// borrowed contents, serialization) works as usual, but there's no `DerefMut` and no way to get
+/// the value back out by value, so methods taking `&mut self` can't be reached:
+///
+/// ```compile_fail
+/// let mut config = rs_json_parser::JsonValue::Null.freeze();
+/// config.set("/debug", rs_json_parser::JsonValue::Boolean(true));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImmutableJson(JsonValue);
+
+impl JsonValue {
+    /// Wraps the value in an `ImmutableJson`, e.g. to hand parsed config around read-only.
+    pub fn freeze(self) -> ImmutableJson {
+        ImmutableJson(self)
+    }
+}
+
+impl Deref for ImmutableJson {
+    type Target = JsonValue;
+
+    fn deref(&self) -> &JsonValue {
+        &self.0
+    }
+}
+
+impl AsRef<JsonValue> for ImmutableJson {
+    fn as_ref(&self) -> &JsonValue {
+        &self.0
+    }
+}
+
+impl Display for ImmutableJson {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+    use crate::tests::sample;
+
+    #[test]
+    fn freeze_should_allow_lookups() {
+        let config = sample().freeze();
+        assert_eq!(config.get_str("/address/city"), Some("New York"));
+        assert_eq!(config.pointer("/marks/1"), Some(&JsonValue::Number(-80.0)));
+        assert_eq!(config.as_object().unwrap().len(), 9);
+        assert_eq!(config.to_string(), config.as_ref().to_string());
+
+        let copy: JsonValue = (*config).clone();
+        assert_eq!(copy, sample());
+    }
+}
@@ -0,0 +1,166 @@
+use crate::ser::{write_number, write_string};
+use crate::JsonValue;
+use crate::prelude::*;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+impl JsonValue {
+    /// A deterministic 64-bit FNV-1a hash of the document's canonical form.
+    ///
+    /// Object keys are hashed in sorted order and `RawNumber`s by their numeric value, so
+    /// documents that compare equal semantically hash the same regardless of key order or
+    /// formatting. The value is stable across runs and platforms.
+    pub fn content_hash(&self) -> u64 {
+        let mut canonical = String::new();
+        self.write_canonical(&mut canonical);
+        canonical.bytes().fold(FNV_OFFSET_BASIS, |hash, b| (hash ^ b as u64).wrapping_mul(FNV_PRIME))
+    }
+
+    /// A cheap key for sorting mixed values deterministically with `sort_by_key`.
+    ///
+    /// Values are ranked by kind first: null 0, booleans 1, numbers 2, strings 3, arrays 4 and
+    /// objects 5. Within a rank the string orders booleans `false` before `true`, numbers
+    /// numerically (including `RawNumber`s), strings by their UTF-8 bytes, and containers by
+    /// their canonical JSON, with object keys sorted.
+    pub fn sort_key(&self) -> (u8, String) {
+        match self {
+            JsonValue::Null => (0, String::new()),
+            JsonValue::Boolean(b) => (1, b.to_string()),
+            JsonValue::Number(n) => (2, sortable_number(*n)),
+            JsonValue::Integer(i) => (2, sortable_number(*i as f64)),
+            JsonValue::RawNumber(s) => (2, sortable_number(s.parse().unwrap_or(f64::NAN))),
+            JsonValue::String(s) => (3, s.clone()),
+            JsonValue::Array(_) | JsonValue::Object(_) => {
+                let mut canonical = String::new();
+                self.write_canonical(&mut canonical);
+                (if matches!(self, JsonValue::Array(_)) { 4 } else { 5 }, canonical)
+            }
+        }
+    }
+
+    fn write_canonical(&self, out: &mut String) {
+        match self {
+            JsonValue::String(s) => write_string(out, s).unwrap(),
+            JsonValue::Number(n) => write_canonical_number(out, *n),
+            JsonValue::Integer(i) => out.push_str(&i.to_string()),
+            JsonValue::RawNumber(s) => match s.parse() {
+                Ok(n) => write_canonical_number(out, n),
+                Err(_) => {
+                    out.push_str("#raw");
+                    write_string(out, s).unwrap();
+                }
+            },
+            JsonValue::Boolean(b) => out.push_str(if *b { "true" } else { "false" }),
+            JsonValue::Null => out.push_str("null"),
+            JsonValue::Array(arr) => {
+                out.push('[');
+                for (i, v) in arr.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    v.write_canonical(out);
+                }
+                out.push(']');
+            }
+            JsonValue::Object(map) => {
+                let mut entries: Vec<_> = map.iter().collect();
+                entries.sort_by(|a, b| a.0.cmp(b.0));
+
+                out.push('{');
+                for (i, (k, v)) in entries.into_iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    write_string(out, k).unwrap();
+                    out.push(':');
+                    v.write_canonical(out);
+                }
+                out.push('}');
+            }
+        }
+    }
+}
+
+/// Writes `n` so that numbers compare equal exactly when their canonical forms do: `-0` is
+/// written as `0`, and non-finite numbers, which JSON can't spell, get tags of their own rather
+/// than the `null` serialization uses.
+fn write_canonical_number(out: &mut String, n: f64) {
+    if n.is_finite() {
+        write_number(out, if n == 0.0 { 0.0 } else { n }).unwrap();
+    } else if n.is_nan() {
+        out.push_str("#nan");
+    } else {
+        out.push_str(if n > 0.0 { "#inf" } else { "#-inf" });
+    }
+}
+
+/// Fixed-width hex of `n`'s bits, flipped so that the strings sort in numeric order. `-0`
+/// sorts with `0`, as they compare equal.
+fn sortable_number(n: f64) -> String {
+    let bits = if n == 0.0 { 0.0_f64 } else { n }.to_bits();
+    let ordered = if bits >> 63 == 1 { !bits } else { bits | 1 << 63 };
+    format!("{:016x}", ordered)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_json, parse_json_with_options, JsonValue, ParserOptions};
+    use crate::prelude::*;
+
+    #[test]
+    fn content_hash_should_ignore_key_order() {
+        let a = parse_json(&mut r#"{"a": 1, "b": {"x": [1, 2], "y": null}}"#).unwrap();
+        let b = parse_json(&mut r#"{ "b" : { "y":null, "x":[1,2] }, "a":1 }"#).unwrap();
+        assert_eq!(a.content_hash(), b.content_hash());
+
+        let raw = parse_json_with_options(&mut r#"{"a": 1.0, "b": {"x": [1, 2], "y": null}}"#, &ParserOptions::new().preserve_number_text(true)).unwrap();
+        assert_eq!(a.content_hash(), raw.content_hash());
+    }
+
+    #[test]
+    fn content_hash_should_treat_zeros_alike() {
+        let zero = JsonValue::Number(0.0);
+        let negative_zero = JsonValue::Number(-0.0);
+        assert_eq!(zero, negative_zero);
+        assert_eq!(zero.content_hash(), negative_zero.content_hash());
+        assert_eq!(zero.sort_key(), negative_zero.sort_key());
+        let raw = JsonValue::RawNumber("-0.0".to_string());
+        assert_eq!(raw.content_hash(), zero.content_hash());
+    }
+
+    #[test]
+    fn content_hash_should_tag_non_finite_numbers() {
+        let null = JsonValue::Null.content_hash();
+        let huge = JsonValue::RawNumber("1e400".to_string());
+        let bogus = JsonValue::RawNumber("abc".to_string());
+        for value in [&huge, &bogus, &JsonValue::Number(f64::INFINITY), &JsonValue::Number(f64::NAN)] {
+            assert_ne!(value.content_hash(), null, "{:?}", value);
+        }
+        assert_ne!(huge.content_hash(), JsonValue::Number(f64::NEG_INFINITY).content_hash());
+        assert_ne!(huge.content_hash(), bogus.content_hash());
+        assert_eq!(huge.content_hash(), JsonValue::Number(f64::INFINITY).content_hash());
+    }
+
+    #[test]
+    fn content_hash_should_detect_changes() {
+        let a = parse_json(&mut r#"{"a": 1, "b": [1, 2]}"#).unwrap();
+        let b = parse_json(&mut r#"{"a": 1, "b": [2, 1]}"#).unwrap();
+        let c = parse_json(&mut r#"{"a": "1", "b": [1, 2]}"#).unwrap();
+        assert_ne!(a.content_hash(), b.content_hash());
+        assert_ne!(a.content_hash(), c.content_hash());
+    }
+
+    #[test]
+    fn sort_key_should_work() {
+        let mut values = parse_json(&mut r#"[{"b": 1}, "b", 10, [2], -1.5, null, true, "a", 9, false, [1, 3], 0]"#)
+            .unwrap();
+        let JsonValue::Array(arr) = &mut values else { unreachable!() };
+        arr.sort_by_key(JsonValue::sort_key);
+        let expected = parse_json(&mut r#"[null, false, true, -1.5, 0, 9, 10, "a", "b", [1, 3], [2], {"b": 1}]"#).unwrap();
+        assert_eq!(values, expected);
+
+        let raw = JsonValue::RawNumber("9.0".to_string());
+        assert_eq!(raw.sort_key(), JsonValue::Number(9.0).sort_key());
+    }
+}
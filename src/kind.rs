@@ -0,0 +1,150 @@
+use core::fmt::{self, Display, Formatter};
+use crate::{JsonValue, Map};
+
+/// The type of a `JsonValue` without its payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum JsonKind {
+    String,
+    Number,
+    Boolean,
+    Null,
+    Array,
+    Object,
+}
+
+impl Display for JsonKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            JsonKind::String => "string",
+            JsonKind::Number => "number",
+            JsonKind::Boolean => "boolean",
+            JsonKind::Null => "null",
+            JsonKind::Array => "array",
+            JsonKind::Object => "object",
+        };
+        f.write_str(name)
+    }
+}
+
+impl JsonValue {
+    pub fn kind(&self) -> JsonKind {
+        match self {
+            JsonValue::String(_) => JsonKind::String,
+            JsonValue::Number(_) | JsonValue::Integer(_) | JsonValue::RawNumber(_) => JsonKind::Number,
+            JsonValue::Boolean(_) => JsonKind::Boolean,
+            JsonValue::Null => JsonKind::Null,
+            JsonValue::Array(_) => JsonKind::Array,
+            JsonValue::Object(_) => JsonKind::Object,
+        }
+    }
+
+    /// Whether every element of an array has the same `JsonKind`; empty arrays count as
+    /// homogeneous. Returns `None` for non-arrays.
+    pub fn is_homogeneous_array(&self) -> Option<bool> {
+        match self {
+            JsonValue::Array(arr) => Some(arr.windows(2).all(|w| w[0].kind() == w[1].kind())),
+            _ => None,
+        }
+    }
+
+    /// Checks the value against a lightweight inline schema.
+    ///
+    /// At leaf positions `shape` holds one of the sentinel strings `"string"`, `"number"`,
+    /// `"bool"`, `"null"` or `"any"`; any other scalar must match exactly. A shape object requires
+    /// each of its keys to be present and match, ignoring extra keys in the value. A shape array
+    /// with one element requires every element to match it, and `[]` accepts any array.
+    pub fn matches_shape(&self, shape: &JsonValue) -> bool {
+        match (shape, self) {
+            (JsonValue::String(sentinel), _) => match sentinel.as_str() {
+                "string" => self.kind() == JsonKind::String,
+                "number" => self.kind() == JsonKind::Number,
+                "bool" => self.kind() == JsonKind::Boolean,
+                "null" => self.kind() == JsonKind::Null,
+                "any" => true,
+                _ => self == shape,
+            },
+            (JsonValue::Array(shape), JsonValue::Array(arr)) => match shape.as_slice() {
+                [] => true,
+                [element] => arr.iter().all(|v| v.matches_shape(element)),
+                _ => false,
+            },
+            (JsonValue::Object(shape), JsonValue::Object(map)) => {
+                shape.iter().all(|(k, s)| map.get(k).is_some_and(|v| v.matches_shape(s)))
+            }
+            (JsonValue::Array(_) | JsonValue::Object(_), _) => false,
+            _ => self == shape,
+        }
+    }
+
+    /// Counts how many values of each kind appear in the tree, including `self`.
+    pub fn kind_histogram(&self) -> Map<JsonKind, usize> {
+        let mut histogram = Map::new();
+        self.tally_kinds(&mut histogram);
+        histogram
+    }
+
+    fn tally_kinds(&self, histogram: &mut Map<JsonKind, usize>) {
+        *histogram.entry(self.kind()).or_insert(0) += 1;
+        match self {
+            JsonValue::Array(arr) => arr.iter().for_each(|v| v.tally_kinds(histogram)),
+            JsonValue::Object(map) => map.values().for_each(|v| v.tally_kinds(histogram)),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+    use crate::tests::sample;
+
+    #[test]
+    fn kind_should_work() {
+        assert_eq!(JsonValue::Null.kind(), JsonKind::Null);
+        assert_eq!(JsonValue::RawNumber("1.0".to_string()).kind(), JsonKind::Number);
+        assert_eq!(sample().kind(), JsonKind::Object);
+    }
+
+    #[test]
+    fn kind_histogram_should_work() {
+        let histogram = sample().kind_histogram();
+        assert_eq!(histogram[&JsonKind::String], 4);
+        assert_eq!(histogram[&JsonKind::Number], 10);
+        assert_eq!(histogram[&JsonKind::Boolean], 2);
+        assert_eq!(histogram[&JsonKind::Null], 1);
+        assert_eq!(histogram[&JsonKind::Array], 3);
+        assert_eq!(histogram[&JsonKind::Object], 5);
+    }
+
+    #[test]
+    fn matches_shape_should_work() {
+        let json = sample();
+        let shape = |s: &str| crate::parse_json(&mut (&*s)).unwrap();
+
+        let matching = shape(r#"{
+            "name": "string",
+            "age": "number",
+            "is_student": "bool",
+            "marks": ["number"],
+            "address": {"city": "string", "zip": "number"},
+            "nested": {"different_element_array": ["any"], "empty_arr": ["string"], "empty_obj": {}}
+        }"#);
+        assert!(json.matches_shape(&matching));
+        assert!(json.matches_shape(&shape("{}")));
+
+        assert!(!json.matches_shape(&shape(r#"{"age": "string"}"#)));
+        assert!(!json.matches_shape(&shape(r#"{"address": {"street": "string"}}"#)));
+        assert!(!json.matches_shape(&shape(r#"{"nested": {"different_element_array": ["number"]}}"#)));
+        assert!(!json.matches_shape(&shape(r#"{"marks": {}}"#)));
+    }
+
+    #[test]
+    fn is_homogeneous_array_should_work() {
+        let json = sample();
+        assert_eq!(json.pointer("/marks").unwrap().is_homogeneous_array(), Some(true));
+        assert_eq!(json.pointer("/nested/different_element_array").unwrap().is_homogeneous_array(), Some(false));
+        assert_eq!(json.pointer("/nested/empty_arr").unwrap().is_homogeneous_array(), Some(true));
+        assert_eq!(json.is_homogeneous_array(), None);
+    }
+}
@@ -0,0 +1,1822 @@
+//! Without the default `std` feature the crate is `no_std` and needs only `alloc`: every
+//! `String`, `Vec` and map it builds is allocated through the `#[global_allocator]`, so an
+//! embedded target routes parsing through its own allocator by installing one there.
+//!
+//! The parser is not generic over an allocator: the `A` parameter of `Vec<T, A>` is still
+//! unstable and `String` has none, so per-parse allocators aren't supported.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::borrow::Cow;
+use core::cell::RefCell;
+use core::str::FromStr;
+use anyhow::Result;
+use winnow::{Parser, PResult};
+use winnow::ascii::{digit1, multispace0, Caseless};
+use winnow::combinator::{alt, cut_err, delimited, eof, fail, opt, preceded, repeat_till, separated, terminated};
+use winnow::error::{ContextError, ErrMode, ErrorKind, ParserError, StrContext, StrContextValue};
+use winnow::stream::{AsChar, Stateful, Stream};
+use winnow::token::{any, one_of, take_till, take_until, take_while};
+use crate::prelude::*;
+
+mod builder;
+mod comment;
+mod convert;
+#[cfg(feature = "chrono")]
+mod datetime;
+mod error;
+mod frozen;
+mod hash;
+mod kind;
+mod options;
+mod patch;
+mod pointer;
+mod prelude;
+mod query;
+mod reuse;
+mod ser;
+mod shared;
+mod span;
+mod stream;
+mod token;
+mod transform;
+mod value;
+mod warning;
+
+pub use builder::{ArrayBuilder, ObjectBuilder};
+pub use comment::{parse_json_with_comments, parse_json_with_trivia_stats, Comments, TriviaStats, ValueComments};
+pub use error::JsonError;
+pub use frozen::ImmutableJson;
+pub use kind::JsonKind;
+pub use options::{DuplicateKeys, InvalidEscapes, NumberMode, ParserOptions};
+pub use reuse::parse_into;
+pub use ser::{PrettyConfig, SerializeChunks, SerializeOptions};
+pub use shared::SharedJsonValue;
+pub use span::{parse_documents, parse_prefix_spanned, Span};
+pub use stream::{parse_array_lazy, ArrayStreamParser, LazyArray};
+pub use token::{Token, Tokenizer};
+pub use warning::{parse_json_with_warnings, Warning, WarningKind};
+use options::ParseState;
+
+/// The map behind `JsonValue::Object`: a `HashMap` with `std`, a `BTreeMap` without it.
+#[cfg(feature = "std")]
+pub type Map<K, V> = std::collections::HashMap<K, V>;
+#[cfg(not(feature = "std"))]
+pub type Map<K, V> = alloc::collections::BTreeMap<K, V>;
+
+type Input<'i, 's> = Stateful<&'i str, &'s ParseState<'s>>;
+
+/// A parsed JSON value.
+///
+/// Parsing, dropping, `max_depth` and `pointer` work at any depth. Everything else, including
+/// `Clone`, `PartialEq`, `Debug`, `Display`, hashing and the tree-walking helpers, recurses once
+/// per level, so a tree nested deeper than the thread's stack allows can only be parsed, measured
+/// and dropped. `ParserOptions::max_depth` rejects such input up front.
+///
+/// Because of the `Drop` impl, a container's contents can't be moved out by destructuring; use
+/// `core::mem::take` or `into_array_iter`/`into_object_iter` instead.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValue {
+    String(String),
+    Number(f64),
+    /// A whole number that fits in `i64`, kept exactly, see `NumberMode::IntFloat`.
+    Integer(i64),
+    /// A number kept as its source literal, see `NumberMode::Raw`.
+    RawNumber(String),
+    Boolean(bool),
+    Null,
+    Array(Vec<JsonValue>),
+    Object(Map<String, JsonValue>),
+}
+
+/// Nested containers are torn down through an explicit stack rather than by recursion, so
+/// dropping a tree as deep as `parse_json` accepts can't overflow the call stack.
+impl Drop for JsonValue {
+    fn drop(&mut self) {
+        let mut pending = Vec::new();
+        dismantle(self, &mut pending);
+        // Each value is emptied before it's dropped, so its own `drop` returns straight away.
+        while let Some(mut value) = pending.pop() {
+            dismantle(&mut value, &mut pending);
+        }
+    }
+}
+
+/// Empties a container in one pass: scalars and empty containers are dropped on the spot and
+/// non-empty containers are moved to `pending`, which stays unallocated for flat values.
+fn dismantle(value: &mut JsonValue, pending: &mut Vec<JsonValue>) {
+    let nested = |v: &JsonValue| match v {
+        JsonValue::Array(arr) => !arr.is_empty(),
+        JsonValue::Object(map) => !map.is_empty(),
+        _ => false,
+    };
+    match value {
+        JsonValue::Array(arr) => pending.extend(core::mem::take(arr).into_iter().filter(nested)),
+        JsonValue::Object(map) => pending.extend(map.drain().map(|(_, v)| v).filter(nested)),
+        _ => {}
+    }
+}
+
+pub fn parse_json(input: &mut &str) -> Result<JsonValue> {
+    parse_json_with_options(input, &ParserOptions::default())
+}
+
+pub fn parse_json_with_options(input: &mut &str, options: &ParserOptions) -> Result<JsonValue> {
+    if let Some(max) = options.max_input_bytes.filter(|max| input.len() > *max) {
+        return Err(JsonError::InputTooLarge { len: input.len(), max }.into());
+    }
+    if options.empty_as_null && input.trim().is_empty() {
+        *input = &input[input.len()..];
+        return Ok(JsonValue::Null);
+    }
+
+    let state = ParseState::new(options);
+    let mut stateful = Input { input: *input, state: &state };
+    let end = eof.context(StrContext::Expected(StrContextValue::Description("end of input")));
+    let ret = terminated(delimited(ws, expect_value, ws), end)
+        .parse_next(&mut stateful)
+        .map_err(|e| match options.max_steps {
+            Some(max_steps) if state.out_of_steps() => JsonError::Timeout { max_steps },
+            _ => syntax_error(input, &stateful, e),
+        })?;
+    *input = stateful.input;
+    Ok(ret)
+}
+
+/// Parses a stream of values separated by optional whitespace, such as `1 true "x" [2]`, up to
+/// the end of `input`. Unlike NDJSON, values don't need to be on separate lines.
+pub fn parse_many(input: &str) -> Result<Vec<JsonValue>> {
+    let options = ParserOptions::default();
+    let state = ParseState::new(&options);
+    let mut stateful = Input { input, state: &state };
+    let (values, _) = preceded(multispace0, repeat_till(0.., terminated(expect_value, multispace0), eof))
+        .parse_next(&mut stateful)
+        .map_err(|e| syntax_error(input, &stateful, e))?;
+    Ok(values)
+}
+
+/// Scans free text, such as a log line, for embedded JSON arrays and objects, returning each
+/// complete one with the byte offset where it starts.
+///
+/// Every `{` or `[` is tried as the start of a value; text that doesn't parse is skipped, and
+/// scanning resumes after each value found, so nested containers aren't reported twice.
+pub fn find_json_values(text: &str) -> Vec<(usize, JsonValue)> {
+    let options = ParserOptions::default();
+    let mut found = Vec::new();
+    let mut offset = 0;
+
+    while let Some(start) = text[offset..].find(['{', '[']).map(|i| offset + i) {
+        let state = ParseState::new(&options);
+        let mut stateful = Input { input: &text[start..], state: &state };
+        match parse_value(&mut stateful) {
+            Ok(value) => {
+                found.push((start, value));
+                offset = text.len() - stateful.input.len();
+            }
+            Err(_) => offset = start + 1,
+        }
+    }
+    found
+}
+
+/// Builds the error for a parse of `source` that failed at `input`, locating it in both the
+/// text and the tree.
+fn syntax_error(source: &str, input: &Input, e: ErrMode<ContextError>) -> JsonError {
+    let message = error_message(e, input.state.rejection.take());
+    JsonError::syntax(source, source.len() - input.input.len(), message).in_container(input.state.error_pointer.take())
+}
+
+/// Lists the tokens that would have been valid at the failure point, unless a label or the
+/// message of a `rejected` error explains it better.
+fn error_message(e: ErrMode<ContextError>, rejection: Option<String>) -> String {
+    let e = e.into_inner().unwrap_or_default();
+    let label = e.context().find_map(|c| match c {
+        StrContext::Label(label) => Some(label.to_string()),
+        _ => None,
+    }).or(rejection);
+    // Only `std` builds of winnow keep the cause behind a `try_map` failure.
+    #[cfg(feature = "std")]
+    let label = label.or_else(|| e.cause().map(|cause| cause.to_string()));
+    let expected: Vec<String> = e.context().filter_map(|c| match c {
+        StrContext::Expected(v) => Some(v.to_string()),
+        _ => None,
+    }).collect();
+
+    match (label, expected.as_slice()) {
+        (Some(label), _) => label,
+        (None, []) => "unexpected input".to_string(),
+        (None, [one]) => format!("expected {}", one),
+        (None, many) => format!("expected one of: {}", many.join(", ")),
+    }
+}
+
+/// Parses one value off the front of `input` and advances it past the value, leaving any
+/// trailing content for the caller.
+pub fn parse_value_partial(input: &mut &str) -> Result<JsonValue> {
+    let options = ParserOptions::default();
+    let state = ParseState::new(&options);
+    let mut stateful = Input { input: *input, state: &state };
+    let ret = preceded(multispace0, expect_value)
+        .parse_next(&mut stateful)
+        .map_err(|e| syntax_error(input, &stateful, e))?;
+    *input = stateful.input;
+    Ok(ret)
+}
+
+/// The outcome of `parse_json_recovering`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recovery {
+    /// Everything that could be parsed, with `null` standing in for malformed values.
+    pub value: JsonValue,
+    pub errors: Vec<JsonError>,
+    /// Whether recovery stopped early because `ParserOptions::max_errors` was reached.
+    pub truncated: bool,
+}
+
+/// Parses as much of `input` as possible, collecting errors instead of stopping at the first.
+///
+/// A malformed element is recorded, skipped up to the next `,` or closing bracket and replaced
+/// by `null`.
+pub fn parse_json_recovering(input: &str, options: &ParserOptions) -> Recovery {
+    let mut state = ParseState::new(options);
+    state.recovering = true;
+    let mut stateful = Input { input, state: &state };
+
+    let value = match preceded(multispace0, expect_value).parse_next(&mut stateful) {
+        Ok(value) => value,
+        Err(e) => {
+            state.record_error(stateful.input.len(), None, e);
+            JsonValue::Null
+        }
+    };
+    if !state.truncated.get() {
+        let end = eof.context(StrContext::Expected(StrContextValue::Description("end of input")));
+        if let Err(e) = preceded(multispace0, end).parse_next(&mut stateful) {
+            state.record_error(stateful.input.len(), None, e);
+        }
+    }
+
+    let errors = state.errors.take()
+        .into_iter()
+        .map(|(rest, container, message)| JsonError::syntax(input, input.len() - rest, message).in_container(container))
+        .collect();
+    Recovery { value, errors, truncated: state.truncated.get() }
+}
+
+/// Counts gathered by `parse_with_stats`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParseStats {
+    pub strings: usize,
+    pub numbers: usize,
+    pub booleans: usize,
+    pub nulls: usize,
+    pub arrays: usize,
+    pub objects: usize,
+    /// The deepest nesting reached, counted like `JsonValue::max_depth`.
+    pub max_depth: usize,
+    /// Bytes consumed, including surrounding whitespace.
+    pub bytes: usize,
+}
+
+impl ParseStats {
+    fn record(&mut self, value: &JsonValue, depth: usize) {
+        match value.kind() {
+            JsonKind::String => self.strings += 1,
+            JsonKind::Number => self.numbers += 1,
+            JsonKind::Boolean => self.booleans += 1,
+            JsonKind::Null => self.nulls += 1,
+            JsonKind::Array => self.arrays += 1,
+            JsonKind::Object => self.objects += 1,
+        }
+        self.max_depth = self.max_depth.max(depth);
+    }
+}
+
+/// Parses like `parse_json`, also counting the values it creates as it goes.
+pub fn parse_with_stats(input: &str) -> Result<(JsonValue, ParseStats)> {
+    let options = ParserOptions::default();
+    let mut state = ParseState::new(&options);
+    state.stats = Some(RefCell::new(ParseStats::default()));
+    let mut stateful = Input { input, state: &state };
+
+    let end = eof.context(StrContext::Expected(StrContextValue::Description("end of input")));
+    let value = terminated(delimited(multispace0, expect_value, multispace0), end)
+        .parse_next(&mut stateful)
+        .map_err(|e| syntax_error(input, &stateful, e))?;
+
+    let mut stats = state.stats.take().unwrap_or_default().into_inner();
+    stats.bytes = input.len();
+    Ok((value, stats))
+}
+
+/// Parses like `parse_json`, also returning the JSON Pointer to the most deeply nested leaf, a
+/// scalar or empty container, for tracking down pathologically nested sections. Of several
+/// equally deep leaves, the first in the source wins.
+pub fn parse_with_deepest_path(input: &str) -> Result<(JsonValue, String)> {
+    let options = ParserOptions::default();
+    let mut state = ParseState::new(&options);
+    state.deepest = Some(RefCell::new((0, String::new())));
+    let mut stateful = Input { input, state: &state };
+
+    let end = eof.context(StrContext::Expected(StrContextValue::Description("end of input")));
+    let value = terminated(delimited(multispace0, expect_value, multispace0), end)
+        .parse_next(&mut stateful)
+        .map_err(|e| syntax_error(input, &stateful, e))?;
+
+    let (_, pointer) = state.deepest.take().unwrap_or_default().into_inner();
+    Ok((value, pointer))
+}
+
+/// Parses UTF-8 encoded bytes, reporting the first invalid sequence as a `JsonError::Syntax`
+/// at its byte offset.
+pub fn parse_bytes(input: &[u8]) -> Result<JsonValue> {
+    let s = core::str::from_utf8(input).map_err(|e| {
+        let valid = core::str::from_utf8(&input[..e.valid_up_to()]).expect("the prefix was just validated");
+        JsonError::syntax(valid, valid.len(), "invalid UTF-8")
+    })?;
+    parse_json(&mut (&*s))
+}
+
+/// Checks that `input` is a single valid JSON document, discarding the parsed value.
+pub fn validate(input: &str) -> Result<()> {
+    parse_json(&mut (&*input)).map(drop)
+}
+
+/// A quick yes/no for batch pre-filtering: checks that `input` is UTF-8, the cheaper test,
+/// before running `validate`.
+pub fn is_parseable(input: &[u8]) -> bool {
+    core::str::from_utf8(input).is_ok_and(|s| validate(s).is_ok())
+}
+
+/// Parses `input` as a bare JSON integer such as `42` or `-7`, returning it as an `i64`.
+///
+/// Unlike `parse_json`, fractions and exponents such as `42.0` or `4e2` are rejected, as are
+/// integers that don't fit in an `i64`.
+pub fn parse_strict_integer(input: &str) -> Result<i64> {
+    let options = ParserOptions::default();
+    let state = ParseState::new(&options);
+    let mut stateful = Input { input, state: &state };
+
+    let integer = (opt('-'), digit1).take().context(StrContext::Expected(StrContextValue::Description("integer")));
+    let end = eof.context(StrContext::Expected(StrContextValue::Description("end of input")));
+    let literal = delimited(multispace0, integer, terminated(multispace0, end))
+        .parse_next(&mut stateful)
+        .map_err(|e| syntax_error(input, &stateful, e))?;
+
+    let offset = input.len() - input.trim_start().len();
+    Ok(literal.parse().map_err(|_| JsonError::syntax(input, offset, "integer out of range"))?)
+}
+
+/// Parses one JSON number off the front of `input` straight into `T`, such as `i64` or `f32`,
+/// and advances `input` past it, leaving any trailing content for the caller.
+///
+/// The literal must match the JSON number grammar and is then converted with `T::from_str`, so
+/// a literal `T` can't represent, like `300` for `u8` or `1.5` for `i64`, is a syntax error.
+pub fn parse_number_as<T: FromStr>(input: &mut &str) -> Result<T> {
+    let options = ParserOptions::default();
+    let state = ParseState::new(&options);
+    let mut stateful = Input { input: *input, state: &state };
+
+    let number = alt((parse_scientific_notation, parse_number)).take()
+        .context(StrContext::Expected(StrContextValue::Description("number")));
+    let literal = preceded(multispace0, cut_err(number))
+        .parse_next(&mut stateful)
+        .map_err(|e| syntax_error(input, &stateful, e))?;
+
+    let offset = input.len() - stateful.input.len() - literal.len();
+    let ret = literal.parse().map_err(|_| {
+        let message = format!("number `{}` doesn't fit in {}", literal, core::any::type_name::<T>());
+        JsonError::syntax(input, offset, message)
+    })?;
+    *input = stateful.input;
+    Ok(ret)
+}
+
+/// Parses a JSON array of numbers, such as `[1, 2.5, -3e2]`, straight into a `Vec<f64>`
+/// without building a `JsonValue` for each element.
+pub fn parse_f64_list(input: &str) -> Result<Vec<f64>> {
+    let options = ParserOptions::default();
+    let state = ParseState::new(&options);
+    let mut stateful = Input { input, state: &state };
+
+    let number = cut_err(alt((parse_scientific_notation, parse_number))
+        .context(StrContext::Expected(StrContextValue::Description("number"))));
+    let close = cut_err(preceded(multispace0, ']')).context(expected(']')).context(expected(','));
+    let numbers = alt((
+        ']'.map(|_| Vec::new()),
+        terminated(separated(1.., number, (multispace0, ',', multispace0)), close),
+    ));
+    let list = preceded(cut_err('['.context(expected('['))), preceded(multispace0, numbers));
+
+    let end = eof.context(StrContext::Expected(StrContextValue::Description("end of input")));
+    let ret = terminated(delimited(multispace0, list, multispace0), end)
+        .parse_next(&mut stateful)
+        .map_err(|e| syntax_error(input, &stateful, e))?;
+    Ok(ret)
+}
+
+/// Parses `s` as a single JSON number literal with nothing around it.
+pub(crate) fn parse_number_str(s: &str) -> Option<f64> {
+    let options = ParserOptions::default();
+    let state = ParseState::new(&options);
+    let ret = terminated(alt((parse_scientific_notation, parse_number)), eof)
+        .parse_next(&mut Input { input: s, state: &state });
+    ret.ok()
+}
+
+/// Fast path for machine-generated JSON that carries no insignificant whitespace.
+///
+/// Falls back to `parse_json` as soon as the compact grammar fails, e.g. on the first space.
+pub fn parse_json_compact(input: &mut &str) -> Result<JsonValue> {
+    let options = ParserOptions::default();
+    let mut state = ParseState::new(&options);
+    state.compact = true;
+    let mut stateful = Input { input: *input, state: &state };
+
+    let ret = terminated(parse_value, eof).parse_next(&mut stateful);
+    match ret {
+        Ok(ret) => {
+            *input = stateful.input;
+            Ok(ret)
+        }
+        Err(_) => parse_json(input),
+    }
+}
+
+fn parse_null(input: &mut Input) -> PResult<()> {
+    "null".value(()).parse_next(input)
+}
+
+fn parse_string(input: &mut Input) -> PResult<String> {
+    '"'.parse_next(input)?;
+    let mut ret = input.state.new_string();
+
+    loop {
+        let chunk = take_till(0.., ['"', '\\']).parse_next(input)?;
+        ret.push_str(chunk);
+
+        match any.parse_next(input)? {
+            '"' => return Ok(ret),
+            _ => match input.state.options.invalid_escapes {
+                InvalidEscapes::Reject => ret.push(parse_escape(input)?),
+                mode => match opt(parse_escape).parse_next(input)? {
+                    Some(c) => ret.push(c),
+                    None if mode == InvalidEscapes::PassThrough => {
+                        ret.push('\\');
+                        ret.push(any.parse_next(input)?);
+                    }
+                    None => {
+                        any.parse_next(input)?;
+                        ret.push(char::REPLACEMENT_CHARACTER);
+                    }
+                },
+            },
+        }
+    }
+}
+
+fn parse_escape(input: &mut Input) -> PResult<char> {
+    alt((
+        '"'.value('"'),
+        '\\'.value('\\'),
+        '/'.value('/'),
+        'b'.value('\u{08}'),
+        'f'.value('\u{0C}'),
+        'n'.value('\n'),
+        'r'.value('\r'),
+        't'.value('\t'),
+        preceded('u', parse_unicode_escape),
+    )).parse_next(input)
+}
+
+fn parse_unicode_escape(input: &mut Input) -> PResult<char> {
+    let high = parse_hex4(input)?;
+
+    let code = if (0xD800..0xDC00).contains(&high) {
+        let low = preceded("\\u", parse_hex4)
+            .verify(|low| (0xDC00..0xE000).contains(low))
+            .parse_next(input)?;
+        0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+    } else {
+        high
+    };
+
+    match char::from_u32(code) {
+        Some(c) => Ok(c),
+        None => fail.parse_next(input),
+    }
+}
+
+fn parse_hex4(input: &mut Input) -> PResult<u32> {
+    take_while(4, AsChar::is_hex_digit)
+        .try_map(|s| u32::from_str_radix(s, 16))
+        .parse_next(input)
+}
+
+fn parse_number(input: &mut Input) -> PResult<f64> {
+    if input.state.options.lenient {
+        return parse_lenient_number(input);
+    }
+
+    let sign = opt("-").map(|x| x.is_some()).parse_next(input)?;
+    let num = parse_digits.try_map(|s| s.parse::<f64>()).parse_next(input)?;
+    let ret: Result<(), ErrMode<ContextError>> = ".".value(()).parse_next(input);
+
+    if ret.is_ok() {
+        let frac = parse_digits.parse_next(input)?;
+        let fraction_length = frac.to_string().len();
+        let v = frac.parse::<f64>().unwrap();
+        let fraction_value = v / 10_f64.powi(fraction_length as i32);
+
+        let v = num + fraction_value;
+        Ok(if sign { -v } else { v })
+    } else {
+        Ok(if sign { -num } else { num })
+    }
+}
+
+/// Like `parse_number`, but also accepts a leading `+`, a leading dot (`.5`) and a trailing dot (`5.`).
+fn parse_lenient_number(input: &mut Input) -> PResult<f64> {
+    let sign = opt(one_of(['+', '-'])).map(|c| c == Some('-')).parse_next(input)?;
+    let int = opt(parse_digits).parse_next(input)?;
+    let frac = match int {
+        Some(_) => opt(preceded('.', opt(parse_digits))).parse_next(input)?.flatten(),
+        None => Some(preceded('.', parse_digits).parse_next(input)?),
+    };
+
+    let num = int.map_or(0.0, |s| s.parse::<f64>().unwrap());
+    let fraction_value = frac.map_or(0.0, |s| s.parse::<f64>().unwrap() / 10_f64.powi(s.len() as i32));
+
+    let v = num + fraction_value;
+    Ok(if sign { -v } else { v })
+}
+
+/// Matches a run of digits, allowing `_` between digit groups in lenient mode.
+fn parse_digits<'i>(input: &mut Input<'i, '_>) -> PResult<Cow<'i, str>> {
+    if input.state.options.lenient {
+        let ret = separated(1.., digit1, '_').map(|()| ()).take().parse_next(input)?;
+        Ok(Cow::Owned(ret.replace('_', "")))
+    } else {
+        digit1.map(Cow::Borrowed).parse_next(input)
+    }
+}
+
+/// In lenient mode the literals match in any case, such as `True` or `FALSE`.
+fn parse_boolean(input: &mut Input) -> PResult<bool> {
+   if input.state.options.lenient {
+       return alt((Caseless("true").value(true), Caseless("false").value(false))).parse_next(input);
+   }
+   alt(("true", "false")).parse_to().parse_next(input)
+}
+
+/// A container whose elements are still being parsed, see `parse_value`.
+enum Frame {
+    Array(Vec<JsonValue>),
+    /// The object so far, the key of the value being parsed, and the keys whose values were
+    /// already gathered into an array by `DuplicateKeys::CollectIntoArray`.
+    Object(Map<String, JsonValue>, String, Vec<String>),
+}
+
+/// Parses any value.
+///
+/// Nested containers are tracked on an explicit heap-allocated stack of `Frame`s rather than
+/// by recursion, so arbitrarily deep input (within memory) can't overflow the call stack.
+///
+/// A failure inside a container notes that container's pointer for the error, see `fail_in`.
+fn parse_value(input: &mut Input) -> PResult<JsonValue> {
+    let mut stack = Vec::new();
+    let ret = parse_nested(input, &mut stack);
+    if ret.is_err() && !stack.is_empty() {
+        input.state.fail_in(|| container_pointer(&stack));
+    }
+    ret
+}
+
+fn parse_nested(input: &mut Input, stack: &mut Vec<Frame>) -> PResult<JsonValue> {
+    'value: loop {
+        if !input.state.add_step() {
+            return cut_err(fail.context(StrContext::Label("step budget exceeded"))).parse_next(input);
+        }
+        if let Some(comments) = &input.state.comments {
+            ws(input)?;
+            comments.borrow_mut().value_start(frame_pointer(stack));
+        }
+        let start = input.checkpoint();
+        let start_remaining = input.input.len();
+        let mut value = match opt(preceded(ws, one_of(['[', '{']))).parse_next(input)? {
+            Some('[') => {
+                ws(input)?;
+                if opt(']').parse_next(input)?.is_none() {
+                    if !input.state.can_nest(stack.len()) {
+                        return cut_err(fail.context(StrContext::Label("depth limit exceeded"))).parse_next(input);
+                    }
+                    if let Some(spans) = &input.state.spans {
+                        spans.borrow_mut().open(start_remaining, member_key(stack));
+                    }
+                    stack.push(Frame::Array(input.state.new_array()));
+                    continue 'value;
+                }
+                if let Some(comments) = &input.state.comments {
+                    comments.borrow_mut().container_end(frame_pointer(stack));
+                } else {
+                    ws(input)?;
+                }
+                JsonValue::Array(Vec::new())
+            }
+            Some(_) => {
+                ws(input)?;
+                if opt('}').parse_next(input)?.is_none() {
+                    if !input.state.can_nest(stack.len()) {
+                        return cut_err(fail.context(StrContext::Label("depth limit exceeded"))).parse_next(input);
+                    }
+                    if let Some(key) = parse_next_key(input, || frame_pointer(stack))? {
+                        if let Some(spans) = &input.state.spans {
+                            spans.borrow_mut().open(start_remaining, member_key(stack));
+                        }
+                        stack.push(Frame::Object(input.state.new_object(), key, Vec::new()));
+                        continue 'value;
+                    }
+                }
+                if let Some(comments) = &input.state.comments {
+                    comments.borrow_mut().container_end(frame_pointer(stack));
+                } else {
+                    ws(input)?;
+                }
+                JsonValue::Object(Map::new())
+            }
+            None if stack.is_empty() => parse_scalar(input)?,
+            None if input.state.options.sparse_arrays
+                && matches!(stack.last(), Some(Frame::Array(_)))
+                && input.input.trim_start().starts_with(',') => JsonValue::Null,
+            None => {
+                let ret = cut_err(alt((parse_scalar, fail.context(StrContext::Expected(StrContextValue::Description("value"))))))
+                    .parse_next(input);
+                match ret {
+                    Ok(value) => value,
+                    Err(e) if input.state.recovering => {
+                        recover(input, e, container_pointer(stack));
+                        JsonValue::Null
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        };
+
+        if let Some(spans) = &input.state.spans {
+            spans.borrow_mut().leaf(start_remaining, input.input.len(), member_key(stack));
+        }
+        if let Some(deepest) = &input.state.deepest {
+            let mut deepest = deepest.borrow_mut();
+            if stack.len() > deepest.0 {
+                *deepest = (stack.len(), frame_pointer(stack));
+            }
+        }
+        if let Some(hook) = &input.state.options.on_scalar {
+            if !matches!(value, JsonValue::Array(_) | JsonValue::Object(_)) {
+                if let Err(reason) = (hook.0)(&frame_pointer(stack), &value) {
+                    input.reset(&start);
+                    ws(input)?;
+                    return Err(rejected(input, reason));
+                }
+            }
+        }
+
+        loop {
+            if input.state.truncated.get() {
+                return Ok(unwind(input.state.options.duplicate_keys, core::mem::take(stack), value));
+            }
+            if !input.state.add_node() {
+                return cut_err(fail.context(StrContext::Label("node limit exceeded"))).parse_next(input);
+            }
+            if let Some(stats) = &input.state.stats {
+                stats.borrow_mut().record(&value, stack.len() + 1);
+            }
+            if let Some(comments) = &input.state.comments {
+                comments.borrow_mut().value_end(frame_pointer(stack));
+            }
+
+            let close = match stack.last_mut() {
+                None => return Ok(value),
+                Some(Frame::Array(arr)) => {
+                    arr.push(value);
+                    ']'
+                }
+                Some(Frame::Object(map, key, collected)) => {
+                    let policy = input.state.options.duplicate_keys;
+                    insert_member(policy, map, collected, core::mem::take(key), value);
+                    '}'
+                }
+            };
+            if parse_separator(input, close, || container_pointer(stack))? {
+                if close == ']' {
+                    continue 'value;
+                }
+                let key_start = input.input.len();
+                if let Some(next) = parse_next_key(input, || container_pointer(stack))? {
+                    if let Some(Frame::Object(map, key, _)) = stack.last_mut() {
+                        if map.contains_key(&next) {
+                            input.state.warn(key_start, WarningKind::DuplicateKey(next.clone()));
+                        }
+                        *key = next;
+                    }
+                    continue 'value;
+                }
+            }
+
+            if let Some(spans) = &input.state.spans {
+                spans.borrow_mut().close(input.input.len());
+            }
+            value = match stack.pop() {
+                Some(Frame::Array(arr)) => JsonValue::Array(arr),
+                Some(Frame::Object(map, ..)) => JsonValue::Object(map),
+                None => unreachable!("a container was just closed"),
+            };
+            if let Some(comments) = &input.state.comments {
+                comments.borrow_mut().container_end(frame_pointer(stack));
+            }
+        }
+    }
+}
+
+/// The key of the value about to be added to the innermost open container, if it's an object.
+fn member_key(stack: &[Frame]) -> Option<String> {
+    match stack.last() {
+        Some(Frame::Object(_, key, _)) => Some(key.clone()),
+        _ => None,
+    }
+}
+
+/// The JSON Pointer of the innermost open container itself.
+fn container_pointer(stack: &[Frame]) -> String {
+    frame_pointer(&stack[..stack.len().saturating_sub(1)])
+}
+
+/// The JSON Pointer of the value about to be added to the innermost open container.
+fn frame_pointer(stack: &[Frame]) -> String {
+    let mut pointer = String::new();
+    for frame in stack {
+        pointer.push('/');
+        match frame {
+            Frame::Array(arr) => pointer.push_str(&arr.len().to_string()),
+            Frame::Object(_, key, _) => pointer.push_str(&pointer::escape(key)),
+        }
+    }
+    pointer
+}
+
+/// Adds an object member, resolving a repeated key according to `policy`.
+fn insert_member(
+    policy: DuplicateKeys,
+    map: &mut Map<String, JsonValue>,
+    collected: &mut Vec<String>,
+    key: String,
+    value: JsonValue,
+) {
+    match map.get_mut(&key) {
+        Some(existing) if policy == DuplicateKeys::CollectIntoArray => {
+            if collected.contains(&key) {
+                if let JsonValue::Array(values) = existing {
+                    values.push(value);
+                }
+            } else {
+                let first = core::mem::replace(existing, JsonValue::Null);
+                *existing = JsonValue::Array(vec![first, value]);
+                collected.push(key);
+            }
+        }
+        _ => {
+            map.insert(key, value);
+        }
+    }
+}
+
+/// Closes every open container around `value`, for when recovery gives up part way through.
+fn unwind(policy: DuplicateKeys, mut stack: Vec<Frame>, mut value: JsonValue) -> JsonValue {
+    while let Some(frame) = stack.pop() {
+        value = match frame {
+            Frame::Array(mut arr) => {
+                arr.push(value);
+                JsonValue::Array(arr)
+            }
+            Frame::Object(mut map, key, mut collected) => {
+                insert_member(policy, &mut map, &mut collected, key, value);
+                JsonValue::Object(map)
+            }
+        };
+    }
+    value
+}
+
+/// Parses the `,` or `close` after a container element, returning whether another element
+/// follows. In recovery mode a malformed separator is recorded and skipped.
+///
+/// `container` gives the pointer of the container being parsed, for errors.
+fn parse_separator(input: &mut Input, close: char, container: impl Fn() -> String) -> PResult<bool> {
+    if !input.state.add_step() {
+        return cut_err(fail.context(StrContext::Label("step budget exceeded"))).parse_next(input);
+    }
+    let ret = cut_err(preceded(ws, one_of([',', close])).context(expected(close)).context(expected(',')))
+        .parse_next(input);
+    let sep = match ret {
+        Ok(sep) => sep,
+        Err(e) if input.state.recovering => {
+            if !recover(input, e, container()) {
+                return Ok(false);
+            }
+            opt(one_of([',', ']', '}'])).parse_next(input)?.unwrap_or(close)
+        }
+        Err(e) => return Err(e),
+    };
+    if sep != ',' && input.state.comments.is_some() {
+        // Comments after the bracket are placed once the container is complete.
+        return Ok(false);
+    }
+    let comma = input.input.len() + 1;
+    ws(input)?;
+    if sep == ',' && input.state.warnings.is_some() && opt(close).parse_next(input)?.is_some() {
+        input.state.warn(comma, WarningKind::TrailingComma);
+        ws(input)?;
+        return Ok(false);
+    }
+    if sep == ',' && close == ']' && input.state.options.sparse_arrays && opt(']').parse_next(input)?.is_some() {
+        ws(input)?;
+        return Ok(false);
+    }
+    Ok(sep == ',')
+}
+
+/// The key of the next object entry, or `None` when recovery skipped to the end of the object.
+/// `container` gives the pointer of the object, for errors.
+fn parse_next_key(input: &mut Input, container: impl Fn() -> String) -> PResult<Option<String>> {
+    loop {
+        match parse_key(input) {
+            Ok(key) => return Ok(Some(key)),
+            Err(e) if input.state.recovering => {
+                if !recover(input, e, container()) {
+                    return Ok(None);
+                }
+                let sep = opt(one_of([',', ']', '}'])).parse_next(input)?;
+                ws(input)?;
+                if sep != Some(',') {
+                    return Ok(None);
+                }
+            }
+            Err(e) => {
+                input.state.fail_in(container);
+                return Err(e);
+            }
+        }
+    }
+}
+
+/// Records a recoverable error and skips past the malformed input, returning `false` once
+/// `max_errors` is hit and recovery stops.
+fn recover(input: &mut Input, e: ErrMode<ContextError>, container: String) -> bool {
+    if !input.state.record_error(input.input.len(), Some(container), e) {
+        return false;
+    }
+    skip_garbage(input);
+    true
+}
+
+/// Skips up to the next `,`, `]` or `}` that isn't nested or inside a string.
+fn skip_garbage(input: &mut Input) {
+    let mut depth = 0_usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut end = input.input.len();
+
+    for (i, c) in input.input.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '[' | '{' => depth += 1,
+            ']' | '}' if depth > 0 => depth -= 1,
+            ',' | ']' | '}' => {
+                end = i;
+                break;
+            }
+            _ => {}
+        }
+    }
+    input.next_slice(end);
+}
+
+fn parse_scalar(input: &mut Input) -> PResult<JsonValue> {
+    alt((
+        parse_null.value(JsonValue::Null),
+        parse_string.map(JsonValue::String),
+        parse_json_number,
+        parse_boolean.map(JsonValue::Boolean),
+    )).parse_next(input)
+}
+
+/// An object key and its colon, with the whitespace around them.
+fn parse_key(input: &mut Input) -> PResult<String> {
+    let key = cut_err(alt((parse_string, fail.context(StrContext::Expected(StrContextValue::Description("string"))))))
+        .parse_next(input)?;
+    if let Some(comments) = &input.state.comments {
+        comments.borrow_mut().key_start();
+    }
+    cut_err(preceded(ws, ':').context(expected(':'))).parse_next(input)?;
+    ws(input)?;
+    let options = input.state.options;
+    let key = match &options.key_rewrite {
+        Some(rewrite) => (rewrite.0)(&key),
+        None => key,
+    };
+    match options.normalize_keys {
+        Some(normalize) => Ok(normalize(&key)),
+        None => Ok(key),
+    }
+}
+
+/// Insignificant whitespace, which the `parse_json_compact` fast path doesn't expect at all,
+/// and in JSONC mode comments.
+fn ws(input: &mut Input) -> PResult<()> {
+    if input.state.compact {
+        return Ok(());
+    }
+    if !input.state.options.allow_comments {
+        return multispace0.void().parse_next(input);
+    }
+
+    loop {
+        let space = multispace0.parse_next(input)?;
+        let trivia = input.state.trivia.as_ref();
+        trivia.inspect(|trivia| trivia.borrow_mut().whitespace_bytes += space.len());
+        let comments = input.state.comments.as_ref();
+        if space.contains('\n') {
+            comments.inspect(|comments| comments.borrow_mut().newline());
+        }
+        let comment = if input.input.starts_with("//") {
+            take_till(0.., '\n').parse_next(input)?
+        } else if input.input.starts_with("/*") {
+            let unterminated = StrContext::Label("unterminated comment");
+            ("/*", cut_err(take_until(0.., "*/").context(unterminated)), "*/").take().parse_next(input)?
+        } else {
+            return Ok(());
+        };
+        comments.inspect(|comments| comments.borrow_mut().comment(comment));
+        trivia.inspect(|trivia| trivia.borrow_mut().comments += 1);
+    }
+}
+
+/// `parse_value` at a position where a value is required, so failing there is fatal.
+fn expect_value(input: &mut Input) -> PResult<JsonValue> {
+    cut_err(alt((parse_value, fail.context(StrContext::Expected(StrContextValue::Description("value"))))))
+        .parse_next(input)
+}
+
+fn expected(c: char) -> StrContext {
+    StrContext::Expected(StrContextValue::CharLiteral(c))
+}
+
+fn parse_json_number(input: &mut Input) -> PResult<JsonValue> {
+    let start = input.checkpoint();
+    let (mut v, literal) = alt((parse_scientific_notation, parse_number))
+        .with_taken()
+        .parse_next(input)?;
+
+    if input.state.warnings.is_some() {
+        let digits = literal.trim_start_matches(['-', '+']);
+        if digits.len() > 1 && digits.starts_with('0') && digits.as_bytes()[1].is_ascii_digit() {
+            let remaining = input.input.len() + literal.len();
+            input.state.warn(remaining, WarningKind::LeadingZeros(literal.to_string()));
+        }
+    }
+
+    // Catch `1.2.3` or `1e2e3` here, where we can say what's wrong, rather than as a missing
+    // separator or end of input further up.
+    let end = input.checkpoint();
+    let trailing = take_while(0.., |c: char| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '+' | '-'))
+        .parse_next(input)?;
+    input.reset(&end);
+    if !trailing.is_empty() {
+        return Err(rejected(input, format!("unexpected `{}` after number", trailing)));
+    }
+
+    if input.state.options.numeric_guard {
+        match exact_f64(literal) {
+            Ok(exact) => v = exact,
+            Err(message) => {
+                input.reset(&start);
+                return Err(rejected(input, message));
+            }
+        }
+    }
+
+    match input.state.options.number_mode {
+        NumberMode::F64 => Ok(JsonValue::Number(v)),
+        NumberMode::IntFloat => {
+            let canonical = canonical_number(literal);
+            match canonical.parse() {
+                Ok(i) if !canonical.contains(['.', 'e', 'E']) => Ok(JsonValue::Integer(i)),
+                _ => Ok(JsonValue::Number(v)),
+            }
+        }
+        NumberMode::Raw => Ok(JsonValue::RawNumber(canonical_number(literal))),
+    }
+}
+
+/// Spells a number literal the way JSON requires, so a `RawNumber` read in lenient mode still
+/// serializes as valid JSON: `+1_000.` becomes `1000.0` and `.5` becomes `0.5`. Leading zeros of
+/// the integer part are dropped too. Literals that are already valid JSON are kept as they are.
+fn canonical_number(literal: &str) -> String {
+    let literal = literal.replace('_', "");
+    let unsigned = literal.trim_start_matches(['+', '-']);
+    let (mantissa, exponent) = unsigned.split_at(unsigned.find(['e', 'E']).unwrap_or(unsigned.len()));
+    let (int, frac) = match mantissa.split_once('.') {
+        Some((int, frac)) => (int, Some(frac)),
+        None => (mantissa, None),
+    };
+
+    let mut ret = String::with_capacity(literal.len() + 2);
+    if literal.starts_with('-') {
+        ret.push('-');
+    }
+    match int.trim_start_matches('0') {
+        "" => ret.push('0'),
+        int => ret.push_str(int),
+    }
+    if let Some(frac) = frac {
+        ret.push('.');
+        ret.push_str(if frac.is_empty() { "0" } else { frac });
+    }
+    ret.push_str(exponent);
+    ret
+}
+
+/// A fatal error at the current position carrying a message built at runtime. The message
+/// waits in the parse state, as winnow's error can only carry it as a `std` error cause.
+fn rejected(input: &Input, message: String) -> ErrMode<ContextError> {
+    *input.state.rejection.borrow_mut() = Some(message);
+    ErrMode::Cut(ContextError::from_error_kind(input, ErrorKind::Verify))
+}
+
+/// The nearest `f64` to a number literal, or why it doesn't represent the literal exactly:
+/// either it's out of range, or it reads back as a different decimal (e.g. integers above 2^53).
+fn exact_f64(literal: &str) -> core::result::Result<f64, String> {
+    let literal = literal.replace('_', "");
+    let v = literal.parse::<f64>().map_err(|_| format!("invalid number `{}`", literal))?;
+    if !v.is_finite() {
+        return Err(format!("number `{}` is out of range", literal));
+    }
+    if canonical_decimal(&literal) != canonical_decimal(&format!("{:e}", v)) {
+        return Err(format!("number `{}` loses precision as f64", literal));
+    }
+    Ok(v)
+}
+
+/// Normalizes a decimal literal to its sign, significant digits and the position of the decimal
+/// point before them, so `1.50`, `15e-1` and `0.15e1` compare equal.
+fn canonical_decimal(literal: &str) -> Option<(bool, String, i64)> {
+    let (mantissa, exp) = match literal.split_once(['e', 'E']) {
+        Some((mantissa, exp)) => (mantissa, exp.parse::<i64>().ok()?),
+        None => (literal, 0),
+    };
+    let (negative, mantissa) = match mantissa.strip_prefix('-') {
+        Some(mantissa) => (true, mantissa),
+        None => (false, mantissa.trim_start_matches('+')),
+    };
+    let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+
+    let digits = format!("{}{}", int, frac);
+    let leading_zeros = digits.len() - digits.trim_start_matches('0').len();
+    let digits = digits.trim_matches('0');
+    if digits.is_empty() {
+        return Some((false, String::new(), 0));
+    }
+    Some((negative, digits.to_string(), int.len() as i64 - leading_zeros as i64 + exp))
+}
+
+fn parse_integer(input: &mut Input) -> PResult<f64> {
+    let opt = opt(one_of(|c| c == '+' || c == '-')).parse_next(input)?;
+    let num = parse_digits.try_map(|s| s.parse::<f64>()).parse_next(input)?;
+
+    match opt {
+        Some('+') => Ok(num),
+        Some('-') => Ok(-num),
+        _ => Ok(num)
+    }
+}
+
+/// A number followed by an exponent; once the `e` is seen, the exponent must have digits.
+fn parse_scientific_notation(input: &mut Input) -> PResult<f64> {
+    let exponent = cut_err(parse_integer.context(StrContext::Expected(StrContextValue::Description("exponent digits"))));
+    let ret = (parse_number, "e", exponent).parse_next(input);
+
+    match ret {
+        Ok((x, _, z)) => {
+            let v = x * 10_f64.powi(z as i32);
+            Ok(v)
+        },
+        Err(e) => Err(e) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub(crate) const SAMPLE: &str = r#"{
+        "name": "John Doe",
+        "age": 30,
+        "is_student": false,
+        "marks": [90.0, -80.0, 85.1],
+        "address": {
+            "city": "New York",
+            "zip": 10001
+        },
+        "nested": {
+            "different_element_array": [1, null, true, "hello", { "a": 1, "s": "str" }],
+            "empty_arr": [],
+            "empty_obj": {}
+        },
+        "small_number": 0.00000000000005,
+        "scientific_number": -1.1e-30,
+        "scientific_number2": -1.1e+1
+    }"#;
+
+    pub(crate) fn sample() -> JsonValue {
+        parse_json(&mut (&*SAMPLE)).unwrap()
+    }
+
+    fn run<O>(mut parser: impl FnMut(&mut Input) -> PResult<O>, input: &str) -> PResult<O> {
+        let options = ParserOptions::default();
+        let state = ParseState::new(&options);
+        parser(&mut Input { input, state: &state })
+    }
+
+    #[test]
+    fn parse_null_should_work() {
+        let input = "null";
+        run(parse_null, input).unwrap();
+    }
+
+    #[test]
+    fn parse_string_should_work() {
+        let input = "\"hello\"";
+        let ret = run(parse_string, input).unwrap();
+        assert_eq!(ret, "hello".to_string());
+    }
+
+    #[test]
+    fn parse_string_escapes_should_work() {
+        let input = r#""http:\/\/x""#;
+        let ret = run(parse_string, input).unwrap();
+        assert_eq!(ret, "http://x".to_string());
+
+        let input = r#""a\"b\\c\n\t\u00e9\ud83d\ude00""#;
+        let ret = run(parse_string, input).unwrap();
+        assert_eq!(ret, "a\"b\\c\n\t\u{e9}\u{1F600}".to_string());
+
+        let input = r#""\x""#;
+        assert!(run(parse_string, input).is_err());
+    }
+
+    #[test]
+    fn parse_number_should_work() {
+        let input = "123.456789";
+        let ret = run(parse_number, input).unwrap();
+        assert_eq!(ret, 123.456789);
+    }
+
+    #[test]
+    fn parse_number_underscores_should_work() {
+        let options = ParserOptions::new().lenient(true);
+        let input = "1_000_000";
+        let ret = parse_json_with_options(&mut (&*input), &options).unwrap();
+        assert_eq!(ret, JsonValue::Number(1000000.0));
+
+        let input = "1_000.000_5e1_0";
+        let ret = parse_json_with_options(&mut (&*input), &options).unwrap();
+        assert_eq!(ret, JsonValue::Number(1000.0005e10));
+
+        let input = "1_000_000";
+        assert!(parse_json(&mut (&*input)).is_err());
+    }
+
+    #[test]
+    fn sparse_arrays_should_work() {
+        let options = ParserOptions::new().sparse_arrays(true);
+        let parse = |input: &str| parse_json_with_options(&mut (&*input), &options).unwrap();
+        assert_eq!(parse("[1,,3]"), parse_json(&mut "[1, null, 3]").unwrap());
+        assert_eq!(parse("[ , 1, 2]"), parse_json(&mut "[null, 1, 2]").unwrap());
+        assert_eq!(parse("[1, ,]"), parse_json(&mut "[1, null]").unwrap());
+        assert_eq!(parse("[1,]"), parse_json(&mut "[1]").unwrap());
+        assert_eq!(parse("[[,],]"), parse_json(&mut "[[null]]").unwrap());
+
+        assert!(parse_json_with_options(&mut r#"{"a": 1,}"#, &options).is_err());
+        assert!(parse_json_with_options(&mut r#"{"a": ,}"#, &options).is_err());
+        for input in ["[1,,3]", "[,1]", "[1,]"] {
+            assert!(parse_json(&mut (&*input)).is_err(), "{}", input);
+            assert!(parse_json_with_options(&mut (&*input), &ParserOptions::new().lenient(true)).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn invalid_escapes_should_work() {
+        let input = r#""a\xb\n\u00e9""#;
+        assert!(parse_json(&mut (&*input)).is_err());
+
+        let options = ParserOptions::new().lenient(true).invalid_escapes(InvalidEscapes::PassThrough);
+        let ret = parse_json_with_options(&mut (&*input), &options).unwrap();
+        assert_eq!(ret, JsonValue::String("a\\xb\né".to_string()));
+
+        let options = ParserOptions::new().lenient(true).invalid_escapes(InvalidEscapes::Replace);
+        let ret = parse_json_with_options(&mut (&*input), &options).unwrap();
+        assert_eq!(ret, JsonValue::String("a\u{FFFD}b\né".to_string()));
+
+        let input = r#""\"#;
+        assert!(parse_json_with_options(&mut (&*input), &options).is_err());
+    }
+
+    #[test]
+    fn parse_lenient_number_should_work() {
+        let options = ParserOptions::new().lenient(true);
+        for (input, expected) in [("+5", 5.0), (".5", 0.5), ("5.", 5.0), ("-.5", -0.5), ("+5.e1", 50.0)] {
+            let ret = parse_json_with_options(&mut (&*input), &options).unwrap();
+            assert_eq!(ret, JsonValue::Number(expected), "{}", input);
+        }
+
+        for input in ["+5", ".5", "5."] {
+            assert!(parse_json(&mut (&*input)).is_err(), "{}", input);
+        }
+        for input in [".", "+", "+."] {
+            assert!(parse_json_with_options(&mut (&*input), &options).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn lenient_booleans_should_ignore_case() {
+        let options = ParserOptions::new().lenient(true);
+        let ret = parse_json_with_options(&mut r#"[True, FALSE, tRuE, false]"#, &options).unwrap();
+        let expected = [true, false, true, false].map(JsonValue::Boolean);
+        assert_eq!(ret, JsonValue::Array(expected.to_vec()));
+
+        for input in ["True", "FALSE"] {
+            assert!(parse_json(&mut (&*input)).is_err(), "{}", input);
+        }
+        assert!(parse_json_with_options(&mut "Truth", &options).is_err());
+    }
+
+    #[test]
+    fn lenient_raw_numbers_should_round_trip() {
+        let options = ParserOptions::new().lenient(true).preserve_number_text(true);
+        for (input, expected) in [
+            ("1_000", "1000"),
+            (".5", "0.5"),
+            ("-.5", "-0.5"),
+            ("5.", "5.0"),
+            ("+5", "5"),
+            ("+1_0.2_5e1_0", "10.25e10"),
+            ("5.e1", "5.0e1"),
+            ("-0.5", "-0.5"),
+            ("1.5e-3", "1.5e-3"),
+        ] {
+            let ret = parse_json_with_options(&mut (&*input), &options).unwrap();
+            assert_eq!(ret, JsonValue::RawNumber(expected.to_string()), "{}", input);
+            let reparsed = parse_json(&mut ret.to_string().as_str()).unwrap();
+            assert_eq!(reparsed.as_f64(), ret.as_f64(), "{}", input);
+            assert!(ret.as_f64().is_some(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn parse_scientific_notation_should_work() {
+        let input = "1.1e-30";
+        let ret = run(parse_scientific_notation, input).unwrap();
+        assert_eq!(ret, 1.1e-30);
+
+        let input = "1.1e+1";
+        let ret = run(parse_scientific_notation, input).unwrap();
+        assert_eq!(ret, 1.1e1);
+    }
+
+    #[test]
+    fn parse_with_deepest_path_should_work() {
+        let (value, pointer) = parse_with_deepest_path(SAMPLE).unwrap();
+        assert_eq!(value, sample());
+        assert_eq!(pointer, "/nested/different_element_array/4/a");
+
+        let (_, pointer) = parse_with_deepest_path(r#"[1, [[]], {"a/b": [2]}]"#).unwrap();
+        assert_eq!(pointer, "/2/a~1b/0");
+        assert_eq!(parse_with_deepest_path(" 7 ").unwrap().1, "");
+        assert!(parse_with_deepest_path("[1,]").is_err());
+    }
+
+    #[test]
+    fn parse_number_as_should_work() {
+        let mut input = " -42, 7";
+        assert_eq!(parse_number_as::<i64>(&mut input).unwrap(), -42);
+        assert_eq!(input, ", 7");
+
+        let mut input = "255";
+        assert_eq!(parse_number_as::<u8>(&mut input).unwrap(), 255);
+        assert_eq!(parse_number_as::<f32>(&mut "2.5e1").unwrap(), 25.0);
+
+        let mut input = "[300]";
+        assert!(parse_number_as::<u8>(&mut input).is_err());
+        assert_eq!(input, "[300]");
+
+        let mut input = " 256";
+        let err = parse_number_as::<u8>(&mut input).unwrap_err().downcast::<JsonError>().unwrap();
+        let JsonError::Syntax { message, offset, .. } = err else { panic!("expected syntax error") };
+        assert_eq!(message, "number `256` doesn't fit in u8");
+        assert_eq!(offset, 1);
+        assert_eq!(input, " 256");
+        assert!(parse_number_as::<i64>(&mut "1.5").is_err());
+    }
+
+    #[test]
+    fn parse_strict_integer_should_work() {
+        assert_eq!(parse_strict_integer("42").unwrap(), 42);
+        assert_eq!(parse_strict_integer(" -7\n").unwrap(), -7);
+        assert_eq!(parse_strict_integer("9223372036854775807").unwrap(), i64::MAX);
+
+        let err = parse_strict_integer("42.0").unwrap_err().downcast::<JsonError>().unwrap();
+        let JsonError::Syntax { message, offset, .. } = err else { panic!("expected syntax error") };
+        assert_eq!(message, "expected end of input");
+        assert_eq!(offset, 2);
+
+        assert!(parse_strict_integer("4e2").is_err());
+        assert!(parse_strict_integer("+1").is_err());
+        assert!(parse_strict_integer("").is_err());
+        let err = parse_strict_integer("9223372036854775808").unwrap_err();
+        assert!(err.to_string().contains("integer out of range"));
+    }
+
+    #[test]
+    fn exponent_without_digits_should_fail() {
+        let cases = [("1e", 2, None), ("1e+", 3, None), ("1e-", 3, None), ("[2.5e, 1]", 5, Some(""))];
+        for (input, offset, container) in cases {
+            let err = parse_json(&mut (&*input)).unwrap_err().downcast::<JsonError>().unwrap();
+            let expected = JsonError::syntax(input, offset, "expected exponent digits");
+            assert_eq!(err, expected.in_container(container.map(String::from)), "{}", input);
+        }
+
+        assert_eq!(parse_json(&mut "1e0").unwrap(), JsonValue::Number(1.0));
+        assert_eq!(parse_json(&mut "-2e+1").unwrap(), JsonValue::Number(-20.0));
+        assert_eq!(parse_number_str("1e"), None);
+    }
+
+    #[test]
+    fn parse_f64_list_should_work() {
+        assert_eq!(parse_f64_list("[1, 2.5, -3e2]").unwrap(), vec![1.0, 2.5, -300.0]);
+        assert_eq!(parse_f64_list(" [ ]\n").unwrap(), Vec::<f64>::new());
+        assert_eq!(parse_f64_list("[0]").unwrap(), vec![0.0]);
+
+        let error = |input: &str| match parse_f64_list(input).unwrap_err().downcast::<JsonError>().unwrap() {
+            JsonError::Syntax { message, offset, .. } => (message, offset),
+            err => panic!("expected syntax error, found {:?}", err),
+        };
+        assert_eq!(error(r#"[1, "2"]"#), ("expected number".to_string(), 4));
+        assert_eq!(error("[1 2]"), ("expected one of: `]`, `,`".to_string(), 3));
+        assert_eq!(error("1"), ("expected `[`".to_string(), 0));
+        assert_eq!(error("[1] x"), ("expected end of input".to_string(), 4));
+    }
+
+    #[test]
+    fn trailing_number_characters_should_fail() {
+        let error = |input: &str| match parse_json(&mut (&*input)).unwrap_err().downcast::<JsonError>().unwrap() {
+            JsonError::Syntax { message, offset, .. } => (message, offset),
+            err => panic!("expected syntax error, found {:?}", err),
+        };
+        assert_eq!(error("1.2.3"), ("unexpected `.3` after number".to_string(), 3));
+        assert_eq!(error("1e2e3"), ("unexpected `e3` after number".to_string(), 3));
+        assert_eq!(error("[1, 2x, 3]"), ("unexpected `x` after number".to_string(), 5));
+        assert_eq!(error("-1-"), ("unexpected `-` after number".to_string(), 2));
+
+        assert!(parse_json(&mut "[1.2, 3e2,4]").is_ok());
+    }
+
+    #[test]
+    fn parse_boolean_should_work() {
+        let input = "true";
+        let ret = run(parse_boolean, input).unwrap();
+        assert!(ret);
+
+        let input = "false";
+        let ret = run(parse_boolean, input).unwrap();
+        assert!(!ret);
+    }
+
+    #[test]
+    fn parse_array_should_work() {
+        let input = "[1, 2, 3]";
+        let ret = run(parse_value, input).unwrap();
+        assert_eq!(ret, JsonValue::Array(vec![JsonValue::Number(1.0), JsonValue::Number(2.0), JsonValue::Number(3.0)]));
+    }
+
+    #[test]
+    fn parse_object_should_work() {
+        let input = r#"{"key": 1}"#;
+        let ret = run(parse_value, input).unwrap();
+        let mut map = Map::new();
+        map.insert("key".to_string(), JsonValue::Number(1.0));
+        assert_eq!(ret, JsonValue::Object(map));
+    }
+
+    #[test]
+    fn parse_deeply_nested_should_work() {
+        let depth = 1_000_000;
+        let input = format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        let ret = parse_json(&mut input.as_str()).unwrap();
+        assert_eq!(ret.max_depth(), depth);
+        assert_eq!(ret.pointer(&"/0".repeat(depth - 1)), Some(&JsonValue::Array(Vec::new())));
+        drop(ret);
+
+        let depth = 100_000;
+        let input = format!("{}{}", r#"{"a": ["#.repeat(depth), "]}".repeat(depth));
+        let ret = parse_json(&mut input.as_str()).unwrap();
+        assert_eq!(ret.max_depth(), 2 * depth);
+    }
+
+    #[test]
+    fn syntax_error_should_list_expected_tokens() {
+        let error = |input: &str| parse_json(&mut (&*input)).unwrap_err().downcast::<JsonError>().unwrap();
+
+        let JsonError::Syntax { message, offset, .. } = error(r#"{"a":1"#) else { panic!("expected syntax error") };
+        assert_eq!(message, "expected one of: `}`, `,`");
+        assert_eq!(offset, 6);
+
+        let JsonError::Syntax { message, offset, .. } = error(r#"{"a" 1}"#) else { panic!("expected syntax error") };
+        assert_eq!(message, "expected `:`");
+        assert_eq!(offset, 5);
+
+        let JsonError::Syntax { message, offset, .. } = error("[1, 2,]") else { panic!("expected syntax error") };
+        assert_eq!(message, "expected value");
+        assert_eq!(offset, 6);
+
+        let JsonError::Syntax { message, .. } = error("[1] 2") else { panic!("expected syntax error") };
+        assert_eq!(message, "expected end of input");
+    }
+
+    #[test]
+    fn syntax_error_should_name_container() {
+        let pointer = |input: &str| {
+            let err = parse_json(&mut (&*input)).unwrap_err().downcast::<JsonError>().unwrap();
+            err.pointer().map(String::from)
+        };
+        assert_eq!(pointer(r#"{"a": {"b" 1}}"#).as_deref(), Some("/a"));
+        assert_eq!(pointer(r#"{"a": [1 2]}"#).as_deref(), Some("/a"));
+        assert_eq!(pointer(r#"{"a": [1, {"b": x}]}"#).as_deref(), Some("/a/1"));
+        assert_eq!(pointer(r#"{"a~b": [1, ]}"#).as_deref(), Some("/a~0b"));
+        assert_eq!(pointer("[1, 2"), Some(String::new()));
+        assert_eq!(pointer("tru"), None);
+        assert_eq!(pointer("[1] 2"), None);
+    }
+
+    #[test]
+    fn parse_many_should_work() {
+        let ret = parse_many(r#"1 true "x" [2]"#).unwrap();
+        assert_eq!(ret, vec![
+            JsonValue::Number(1.0),
+            JsonValue::Boolean(true),
+            JsonValue::String("x".to_string()),
+            JsonValue::Array(vec![JsonValue::Number(2.0)]),
+        ]);
+
+        assert_eq!(parse_many("{}\n\t[]").unwrap().len(), 2);
+        assert!(parse_many("  ").unwrap().is_empty());
+
+        let err = parse_many("1 2 x").unwrap_err().downcast::<JsonError>().unwrap();
+        let JsonError::Syntax { message, offset, .. } = err else { panic!("expected syntax error") };
+        assert_eq!(message, "expected value");
+        assert_eq!(offset, 4);
+    }
+
+    #[test]
+    fn parse_with_stats_should_work() {
+        let (value, stats) = parse_with_stats(SAMPLE).unwrap();
+        assert_eq!(value, sample());
+        assert_eq!(stats, ParseStats {
+            strings: 4,
+            numbers: 10,
+            booleans: 2,
+            nulls: 1,
+            arrays: 3,
+            objects: 5,
+            max_depth: value.max_depth(),
+            bytes: SAMPLE.len(),
+        });
+
+        let (_, stats) = parse_with_stats(" 1 ").unwrap();
+        assert_eq!((stats.numbers, stats.max_depth, stats.bytes), (1, 1, 3));
+        assert!(parse_with_stats("[1,").is_err());
+    }
+
+    #[test]
+    fn find_json_values_should_work() {
+        let line = r#"2024-01-01 INFO [worker] got {"id": 1, "tags": ["a]", "{b"]} then {"id": 2, "nested": {"ok": true}} [done"#;
+        let found = find_json_values(line);
+        assert_eq!(found.len(), 2);
+
+        assert_eq!(found[0].0, 29);
+        assert_eq!(found[0].1, parse_json(&mut r#"{"id": 1, "tags": ["a]", "{b"]}"#).unwrap());
+        assert_eq!(found[1].0, line.find(r#"{"id": 2"#).unwrap());
+        assert_eq!(found[1].1.pointer("/nested/ok"), Some(&JsonValue::Boolean(true)));
+
+        assert_eq!(find_json_values("[1, 2] and [3]").len(), 2);
+        assert!(find_json_values("no json { here [").is_empty());
+    }
+
+    #[test]
+    fn parse_value_partial_should_work() {
+        let mut input = r#"  {"a": [1, 2]} <tail>"#;
+        let ret = parse_value_partial(&mut input).unwrap();
+        assert_eq!(ret, parse_json(&mut r#"{"a": [1, 2]}"#).unwrap());
+        assert_eq!(input, "<tail>");
+
+        let mut input = "42,true";
+        assert_eq!(parse_value_partial(&mut input).unwrap(), JsonValue::Number(42.0));
+        assert_eq!(input, ",true");
+
+        let mut input = "<tail>";
+        assert!(parse_value_partial(&mut input).is_err());
+        assert_eq!(input, "<tail>");
+    }
+
+    #[test]
+    fn parse_bytes_should_work() {
+        let input = br#"{"a": [1, "x"]}"#;
+        let ret = parse_bytes(input).unwrap();
+        assert_eq!(ret, parse_json(&mut r#"{"a": [1, "x"]}"#).unwrap());
+
+        let input = b"{\"a\": \"caf\xC3\x28\"}";
+        let err = parse_bytes(input).unwrap_err().downcast::<JsonError>().unwrap();
+        assert_eq!(err, JsonError::syntax(r#"{"a": "caf"#, 10, "invalid UTF-8"));
+        assert_eq!(err.to_string(), "Failed to parse JSON at line 1, column 11: invalid UTF-8");
+    }
+
+    #[test]
+    fn is_parseable_should_work() {
+        assert!(is_parseable(br#"{"a": [1, "x"]}"#));
+        assert!(is_parseable(SAMPLE.as_bytes()));
+        assert!(!is_parseable(b"{\"a\": \"caf\xC3\x28\"}"));
+        assert!(!is_parseable(br#"{"a": [1, "x"}"#));
+        assert!(!is_parseable(b""));
+
+        assert!(validate("[1, 2]").is_ok());
+        assert!(validate("[1, 2] 3").unwrap_err().to_string().contains("expected end of input"));
+    }
+
+    #[test]
+    fn parse_json_compact_should_work() {
+        let input = r#"{"a":[1,true,null],"b":{"c":"d"}}"#;
+        let ret = parse_json_compact(&mut (&*input)).unwrap();
+        assert_eq!(ret, parse_json(&mut (&*input)).unwrap());
+
+        let input = r#"{"a":[1, true, null], "b" : {"c":"d"}}"#;
+        let ret = parse_json_compact(&mut (&*input)).unwrap();
+        assert_eq!(ret, parse_json(&mut (&*input)).unwrap());
+
+        let input = "[1,";
+        assert!(parse_json_compact(&mut (&*input)).is_err());
+    }
+
+    #[test]
+    fn empty_as_null_should_work() {
+        let options = ParserOptions::new().empty_as_null(true);
+        for input in ["", "  \n\t"] {
+            assert_eq!(parse_json_with_options(&mut (&*input), &options).unwrap(), JsonValue::Null);
+            assert!(parse_json(&mut (&*input)).is_err());
+        }
+    }
+
+    #[test]
+    fn parse_json_recovering_should_work() {
+        let options = ParserOptions::default();
+        let ret = parse_json_recovering(r#"{"a": [1, x, 3], "b" 2, "c": true}"#, &options);
+        let mut expected = Map::new();
+        expected.insert("a".to_string(), JsonValue::Array(vec![JsonValue::Number(1.0), JsonValue::Null, JsonValue::Number(3.0)]));
+        expected.insert("c".to_string(), JsonValue::Boolean(true));
+        assert_eq!(ret.value, JsonValue::Object(expected));
+        assert_eq!(ret.errors.len(), 2);
+        assert_eq!(ret.errors[0].pointer(), Some("/a"));
+        assert_eq!(ret.errors[1].pointer(), Some(""));
+        assert!(!ret.truncated);
+
+        let ret = parse_json_recovering("[1, 2]", &options);
+        assert_eq!(ret.errors, vec![]);
+
+        let ret = parse_json_recovering(r#"[1, ["2\"]", x"#, &options);
+        assert_eq!(ret.value, JsonValue::Array(vec![JsonValue::Number(1.0), JsonValue::Array(vec![JsonValue::String("2\"]".to_string()), JsonValue::Null])]));
+        assert_eq!(ret.errors.len(), 3);
+    }
+
+    #[test]
+    fn max_errors_should_truncate() {
+        let input = "[x, 1, x, x, x, x]";
+
+        let ret = parse_json_recovering(input, &ParserOptions::default());
+        assert_eq!(ret.errors.len(), 5);
+        assert!(!ret.truncated);
+
+        let ret = parse_json_recovering(input, &ParserOptions::new().max_errors(2));
+        assert_eq!(ret.errors.len(), 2);
+        assert!(ret.truncated);
+        let JsonValue::Array(arr) = &ret.value else { panic!("expected array") };
+        assert_eq!(arr[..2], [JsonValue::Null, JsonValue::Number(1.0)]);
+    }
+
+    #[test]
+    fn max_input_bytes_should_work() {
+        let options = ParserOptions::new().max_input_bytes(8);
+
+        let input = "[1, 2]";
+        assert!(parse_json_with_options(&mut (&*input), &options).is_ok());
+
+        let mut input = "[1, 2, 3, oops";
+        let err = parse_json_with_options(&mut input, &options).unwrap_err();
+        assert_eq!(err.downcast::<JsonError>().unwrap(), JsonError::InputTooLarge { len: 14, max: 8 });
+        assert_eq!(input, "[1, 2, 3, oops");
+    }
+
+    #[test]
+    fn max_nodes_should_work() {
+        let options = ParserOptions::new().max_nodes(4);
+
+        // Exactly at the limit: the array and its three elements.
+        let input = "[1, 2, 3]";
+        assert!(parse_json_with_options(&mut (&*input), &options).is_ok());
+
+        // One node over.
+        let input = "[1, 2, 3, 4]";
+        let err = parse_json_with_options(&mut (&*input), &options).unwrap_err();
+        assert!(err.to_string().contains("node limit exceeded"));
+    }
+
+    #[test]
+    fn max_depth_option_should_work() {
+        let options = ParserOptions::new().max_depth(3);
+        for input in ["[[1]]", "[[]]", r#"{"a": {"b": 1}}"#, "[1, [2, {}]]"] {
+            let json = parse_json_with_options(&mut (&*input), &options).unwrap();
+            assert!(json.max_depth() <= 3, "{}", input);
+        }
+
+        // One level deeper than the cap, failing where the too-deep container opens.
+        for (input, offset) in [("[[[1]]]", 3), (r#"{"a": {"b": {"c": 1}}}"#, 13)] {
+            let err = parse_json_with_options(&mut (&*input), &options).unwrap_err();
+            let JsonError::Syntax { message, offset: at, .. } = err.downcast().unwrap() else { panic!("expected syntax error") };
+            assert_eq!((message.as_str(), at), ("depth limit exceeded", offset), "{}", input);
+        }
+
+        let input = "[".repeat(1_000_000);
+        let err = parse_json_with_options(&mut input.as_str(), &ParserOptions::new().max_depth(64)).unwrap_err();
+        assert!(err.to_string().contains("depth limit exceeded"));
+    }
+
+    #[test]
+    fn max_steps_should_work() {
+        let input = format!("[{}]", vec!["{\"a\": [1, 2]}"; 10_000].join(","));
+        let options = ParserOptions::new().max_steps(1_000);
+        let err = parse_json_with_options(&mut input.as_str(), &options).unwrap_err();
+        assert_eq!(err.downcast::<JsonError>().unwrap(), JsonError::Timeout { max_steps: 1_000 });
+
+        let options = ParserOptions::new().max_steps(100_000);
+        assert!(parse_json_with_options(&mut input.as_str(), &options).is_ok());
+        let input = "[1, x]";
+        assert!(matches!(
+            parse_json_with_options(&mut (&*input), &options).unwrap_err().downcast::<JsonError>(),
+            Ok(JsonError::Syntax { .. })
+        ));
+    }
+
+    #[test]
+    fn number_mode_should_work() {
+        let input = "[1, -2.50, 3e2]";
+
+        let options = ParserOptions::new().number_mode(NumberMode::F64);
+        let json = parse_json_with_options(&mut (&*input), &options).unwrap();
+        assert_eq!(json, parse_json(&mut (&*input)).unwrap());
+        assert_eq!(json.pointer("/2"), Some(&JsonValue::Number(300.0)));
+
+        let options = ParserOptions::new().number_mode(NumberMode::Raw);
+        let json = parse_json_with_options(&mut (&*input), &options).unwrap();
+        let raw = |s: &str| JsonValue::RawNumber(s.to_string());
+        assert_eq!(json, JsonValue::Array(vec![raw("1"), raw("-2.50"), raw("3e2")]));
+
+        let options = ParserOptions::new().number_mode(NumberMode::IntFloat);
+        let input = "[1, -2.50, 3e2, 9007199254740993, -0, 1.0, 99999999999999999999]";
+        let json = parse_json_with_options(&mut (&*input), &options).unwrap();
+        let expected = vec![
+            JsonValue::Integer(1),
+            JsonValue::Number(-2.5),
+            JsonValue::Number(300.0),
+            JsonValue::Integer(9007199254740993),
+            JsonValue::Integer(0),
+            JsonValue::Number(1.0),
+            JsonValue::Number(1e20),
+        ];
+        assert_eq!(json, JsonValue::Array(expected));
+        assert_eq!(json.to_string(), "[1,-2.5,300,9007199254740993,0,1,100000000000000000000]");
+        assert_eq!(json.pointer("/3").and_then(JsonValue::as_i64), Some(9007199254740993));
+
+        let options = ParserOptions::new().preserve_number_text(true).preserve_number_text(false);
+        assert_eq!(options.number_mode, NumberMode::F64);
+    }
+
+    #[test]
+    fn numeric_guard_should_work() {
+        let options = ParserOptions::new().numeric_guard(true);
+        let parse = |input: &str| parse_json_with_options(&mut (&*input), &options);
+
+        let json = parse("[9007199254740992, 0.1, 2.72, -1.50, 1e300, 0e5]").unwrap();
+        let expected = [9007199254740992.0, 0.1, 2.72, -1.5, 1e300, 0.0].map(JsonValue::Number);
+        assert_eq!(json, JsonValue::Array(expected.to_vec()));
+
+        let err = parse("[1, 9007199254740993]").unwrap_err().downcast::<JsonError>().unwrap();
+        let JsonError::Syntax { message, offset, .. } = err else { panic!("expected syntax error") };
+        assert_eq!(message, "number `9007199254740993` loses precision as f64");
+        assert_eq!(offset, 4);
+
+        let err = parse(r#"{"x": 1e400}"#).unwrap_err();
+        assert!(err.to_string().contains("number `1e400` is out of range"));
+
+        assert!(parse_json(&mut "9007199254740993").is_ok());
+    }
+
+    #[test]
+    fn duplicate_keys_should_work() {
+        let input = r#"{"h": 1, "h": 2}"#;
+        assert_eq!(parse_json(&mut (&*input)).unwrap(), parse_json(&mut r#"{"h": 2}"#).unwrap());
+
+        let options = ParserOptions::new().duplicate_keys(DuplicateKeys::CollectIntoArray);
+        let json = parse_json_with_options(&mut (&*input), &options).unwrap();
+        assert_eq!(json, parse_json(&mut r#"{"h": [1, 2]}"#).unwrap());
+
+        let input = r#"{"h": [1], "u": {"x": null}, "h": {}, "h": 3, "k": [0]}"#;
+        let json = parse_json_with_options(&mut (&*input), &options).unwrap();
+        assert_eq!(json, parse_json(&mut r#"{"h": [[1], {}, 3], "u": {"x": null}, "k": [0]}"#).unwrap());
+    }
+
+    #[test]
+    fn normalize_keys_should_work() {
+        let options = ParserOptions::new().lowercase_keys();
+
+        let input = r#"{"Name": 1, "Nested": {"KEY": [{"A": null}]}}"#;
+        let json = parse_json_with_options(&mut (&*input), &options).unwrap();
+        assert_eq!(json.pointer("/name"), Some(&JsonValue::Number(1.0)));
+        assert_eq!(json.pointer("/nested/key/0/a"), Some(&JsonValue::Null));
+
+        let input = r#"{"Name": 1, "NAME": 2}"#;
+        let json = parse_json_with_options(&mut (&*input), &options).unwrap();
+        assert_eq!(json, ObjectBuilder::new().key("name", JsonValue::Number(2.0)).build());
+
+        let options = ParserOptions::new().normalize_keys(|k| k.replace('-', "_"));
+        let input = r#"{"max-size": 1}"#;
+        let json = parse_json_with_options(&mut (&*input), &options).unwrap();
+        assert!(json.contains_key("max_size"));
+    }
+
+    #[test]
+    fn key_rewrite_should_work() {
+        let prefix = String::from("x_");
+        let options = ParserOptions::new().key_rewrite(move |k| k.strip_prefix(prefix.as_str()).unwrap_or(k).to_string());
+
+        let input = r#"{"x_id": 1, "x_meta": {"x_tag": "a", "name": "b"}, "y_id": 2}"#;
+        let json = parse_json_with_options(&mut (&*input), &options).unwrap();
+        let expected = r#"{"id": 1, "meta": {"tag": "a", "name": "b"}, "y_id": 2}"#;
+        assert_eq!(json, parse_json(&mut (&*expected)).unwrap());
+
+        let options = options.lowercase_keys();
+        let json = parse_json_with_options(&mut r#"{"x_ID": 1, "id": 2}"#, &options).unwrap();
+        assert_eq!(json, ObjectBuilder::new().key("id", JsonValue::Number(2.0)).build());
+    }
+
+    #[test]
+    fn on_scalar_should_work() {
+        let prefix = "/items/".to_string();
+        let options = ParserOptions::new().on_scalar(move |pointer, value| match value.as_f64() {
+            Some(n) if n < 0.0 && pointer.starts_with(prefix.as_str()) => Err(format!("negative item {} at {}", n, pointer)),
+            _ => Ok(()),
+        });
+
+        let input = r#"{"offset": -1, "items": [1, 2, 3]}"#;
+        assert!(parse_json_with_options(&mut (&*input), &options).is_ok());
+
+        let input = r#"{"items": [1, [2],  -3, 4]}"#;
+        let err = parse_json_with_options(&mut (&*input), &options).unwrap_err();
+        let JsonError::Syntax { message, offset, .. } = err.downcast().unwrap() else { panic!("expected syntax error") };
+        assert_eq!(message, "negative item -3 at /items/2");
+        assert_eq!(offset, 20);
+
+        let options = ParserOptions::new().on_scalar(|pointer, _| match pointer {
+            "/a~1b/0/c~0" => Err("found it".to_string()),
+            _ => Ok(()),
+        });
+        let input = r#"{"a/b": [{"c~": null}]}"#;
+        let err = parse_json_with_options(&mut (&*input), &options).unwrap_err();
+        assert!(err.to_string().contains("found it"));
+    }
+}
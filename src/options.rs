@@ -0,0 +1,346 @@
+use core::cell::{Cell, RefCell};
+use core::fmt::{self, Debug, Formatter};
+use alloc::sync::Arc;
+use winnow::error::{ContextError, ErrMode};
+use crate::{error_message, JsonValue, Map, ParseStats};
+use crate::comment::{CommentCollector, TriviaStats};
+use crate::reuse::Pool;
+use crate::span::SpanRecorder;
+use crate::warning::WarningKind;
+use crate::prelude::*;
+
+/// An error recorded by `parse_json_recovering`, with the length of the input remaining where
+/// it occurred, the pointer of the container it occurred in and its message.
+pub(crate) type RecordedError = (usize, Option<String>, String);
+
+/// See `ParserOptions::on_scalar`.
+type ScalarCheck = dyn Fn(&str, &JsonValue) -> Result<(), String> + Send + Sync;
+
+#[derive(Clone)]
+pub(crate) struct ScalarHook(pub(crate) Arc<ScalarCheck>);
+
+impl Debug for ScalarHook {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("ScalarHook(..)")
+    }
+}
+
+/// See `ParserOptions::key_rewrite`.
+#[derive(Clone)]
+pub(crate) struct KeyRewrite(pub(crate) Arc<dyn Fn(&str) -> String + Send + Sync>);
+
+impl Debug for KeyRewrite {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("KeyRewrite(..)")
+    }
+}
+
+/// How `parse_json_with_options` represents numbers, see `ParserOptions::number_mode`.
+///
+/// There's no decimal mode: exact decimal arithmetic needs a big-decimal type this crate
+/// doesn't depend on. Use `Raw` and hand the literal to such a type instead.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum NumberMode {
+    /// Every number becomes a `JsonValue::Number`.
+    #[default]
+    F64,
+    /// Numbers written without a fraction or exponent that fit in `i64` become a
+    /// `JsonValue::Integer`, exact even beyond 2^53; the rest become a `JsonValue::Number`.
+    IntFloat,
+    /// Every number becomes a `JsonValue::RawNumber` holding its source literal, respelled as
+    /// valid JSON where `lenient` accepted something else, e.g. `1_000` or `.5`.
+    Raw,
+}
+
+/// What `parse_json_with_options` does with a key repeated within one object, see
+/// `ParserOptions::duplicate_keys`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DuplicateKeys {
+    /// The last value replaces the earlier ones.
+    #[default]
+    LastWins,
+    /// The values are gathered, in order, into an array under the key, as for repeated HTTP
+    /// headers. A key that appears once keeps its value as it is.
+    CollectIntoArray,
+}
+
+/// What `parse_json_with_options` does with an unknown escape such as `\x` inside a string, see
+/// `ParserOptions::invalid_escapes`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InvalidEscapes {
+    /// The escape is a syntax error, as JSON requires.
+    #[default]
+    Reject,
+    /// The backslash and the character after it are kept as they are, so `\x` reads as `\x`.
+    PassThrough,
+    /// The backslash and the character after it become U+FFFD REPLACEMENT CHARACTER.
+    Replace,
+}
+
+/// Knobs controlling how `parse_json_with_options` treats its input.
+///
+/// The defaults match `parse_json`.
+#[derive(Debug, Clone, Default)]
+pub struct ParserOptions {
+    max_nodes: Option<usize>,
+    max_depth: Option<usize>,
+    pub(crate) max_steps: Option<usize>,
+    pub(crate) number_mode: NumberMode,
+    pub(crate) duplicate_keys: DuplicateKeys,
+    pub(crate) lenient: bool,
+    pub(crate) allow_comments: bool,
+    pub(crate) sparse_arrays: bool,
+    pub(crate) invalid_escapes: InvalidEscapes,
+    pub(crate) empty_as_null: bool,
+    max_errors: Option<usize>,
+    pub(crate) max_input_bytes: Option<usize>,
+    pub(crate) normalize_keys: Option<fn(&str) -> String>,
+    pub(crate) key_rewrite: Option<KeyRewrite>,
+    pub(crate) on_scalar: Option<ScalarHook>,
+    pub(crate) numeric_guard: bool,
+}
+
+impl ParserOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Caps the total number of values (scalars and containers) a single parse may create.
+    pub fn max_nodes(mut self, max_nodes: usize) -> Self {
+        self.max_nodes = Some(max_nodes);
+        self
+    }
+
+    /// Caps the nesting depth, counted like `JsonValue::max_depth`, so input too deep for the
+    /// recursive parts of the `JsonValue` API is rejected while parsing.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Caps the work a single parse may do, counted in steps of one value or separator, so
+    /// hostile input can't keep the parser busy indefinitely. Exceeding it aborts
+    /// `parse_json_with_options` with `JsonError::Timeout`.
+    pub fn max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = Some(max_steps);
+        self
+    }
+
+    /// Selects how numbers are represented in the parsed value.
+    pub fn number_mode(mut self, mode: NumberMode) -> Self {
+        self.number_mode = mode;
+        self
+    }
+
+    /// Selects how a key repeated within one object is resolved.
+    pub fn duplicate_keys(mut self, policy: DuplicateKeys) -> Self {
+        self.duplicate_keys = policy;
+        self
+    }
+
+    /// Keeps each number's source literal as `JsonValue::RawNumber` so it re-serializes verbatim;
+    /// shorthand for `number_mode(NumberMode::Raw)`.
+    pub fn preserve_number_text(self, preserve: bool) -> Self {
+        self.number_mode(if preserve { NumberMode::Raw } else { NumberMode::F64 })
+    }
+
+    /// Accepts relaxed, non-standard syntax: `1_000` digit separators, `+5`, `.5` or `5.` numbers,
+    /// and `true` and `false` in any case, such as `True` or `FALSE`.
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Accepts JSONC: `//` line and `/* */` block comments wherever whitespace may appear.
+    pub fn allow_comments(mut self, allow_comments: bool) -> Self {
+        self.allow_comments = allow_comments;
+        self
+    }
+
+    /// Reads empty array slots, as in `[1,,3]` or `[,1]`, as `null` the way JavaScript does; a
+    /// single comma right before the `]` is ignored. Much looser than `lenient`, which doesn't
+    /// enable it, and objects are unaffected.
+    pub fn sparse_arrays(mut self, sparse_arrays: bool) -> Self {
+        self.sparse_arrays = sparse_arrays;
+        self
+    }
+
+    /// Selects how unknown escapes in strings are handled, for ingesting data from producers
+    /// that don't escape backslashes properly.
+    pub fn invalid_escapes(mut self, mode: InvalidEscapes) -> Self {
+        self.invalid_escapes = mode;
+        self
+    }
+
+    /// Treats input that is empty or all whitespace as `null` instead of an error.
+    pub fn empty_as_null(mut self, empty_as_null: bool) -> Self {
+        self.empty_as_null = empty_as_null;
+        self
+    }
+
+    /// Stops `parse_json_recovering` once this many errors have been collected.
+    pub fn max_errors(mut self, max_errors: usize) -> Self {
+        self.max_errors = Some(max_errors);
+        self
+    }
+
+    /// Rejects input longer than this many bytes up front, before any parsing.
+    pub fn max_input_bytes(mut self, max_input_bytes: usize) -> Self {
+        self.max_input_bytes = Some(max_input_bytes);
+        self
+    }
+
+    /// Rewrites every object key with `normalize` as it's parsed. Keys that collide afterwards
+    /// are resolved like any duplicate keys, see `duplicate_keys`.
+    pub fn normalize_keys(mut self, normalize: fn(&str) -> String) -> Self {
+        self.normalize_keys = Some(normalize);
+        self
+    }
+
+    /// Renames every object key with `rewrite` as it's parsed, e.g. to strip a prefix captured
+    /// by the closure. Runs before `normalize_keys`; keys that collide afterwards are resolved
+    /// like any duplicate keys.
+    pub fn key_rewrite(mut self, rewrite: impl Fn(&str) -> String + Send + Sync + 'static) -> Self {
+        self.key_rewrite = Some(KeyRewrite(Arc::new(rewrite)));
+        self
+    }
+
+    /// `normalize_keys` with `str::to_lowercase`, for case-insensitive keys.
+    pub fn lowercase_keys(self) -> Self {
+        self.normalize_keys(str::to_lowercase)
+    }
+
+    /// Rejects numbers that overflow `f64` or that `f64` can't hold exactly, such as integers
+    /// above 2^53. Accepted numbers are parsed to the nearest `f64`.
+    pub fn numeric_guard(mut self, numeric_guard: bool) -> Self {
+        self.numeric_guard = numeric_guard;
+        self
+    }
+
+    /// Calls `hook` with the JSON Pointer and value of every scalar as soon as it's parsed. An
+    /// `Err` aborts the parse with a syntax error at the scalar, using the reason as its message.
+    pub fn on_scalar(mut self, hook: impl Fn(&str, &JsonValue) -> Result<(), String> + Send + Sync + 'static) -> Self {
+        self.on_scalar = Some(ScalarHook(Arc::new(hook)));
+        self
+    }
+}
+
+/// Per-parse state threaded through every parser function via `Stateful`.
+#[derive(Debug)]
+pub(crate) struct ParseState<'o> {
+    pub(crate) options: &'o ParserOptions,
+    nodes: Cell<usize>,
+    steps: Cell<usize>,
+    /// Set by `parse_json_compact`: containers skip the whitespace combinators.
+    pub(crate) compact: bool,
+    /// Set by `parse_json_recovering`: errors are recorded and skipped instead of returned.
+    pub(crate) recovering: bool,
+    pub(crate) errors: RefCell<Vec<RecordedError>>,
+    /// The pointer of the container a failed parse stopped in, see `JsonError::Syntax`.
+    pub(crate) error_pointer: RefCell<Option<String>>,
+    /// Set by `rejected`: the message of the fatal error being returned.
+    pub(crate) rejection: RefCell<Option<String>>,
+    pub(crate) truncated: Cell<bool>,
+    /// Set by `parse_with_stats`.
+    pub(crate) stats: Option<RefCell<ParseStats>>,
+    /// Set by `parse_prefix_spanned`.
+    pub(crate) spans: Option<RefCell<SpanRecorder>>,
+    /// Set by `parse_json_with_warnings`: tolerated oddities, each with the length of the input
+    /// remaining where it occurred.
+    pub(crate) warnings: Option<RefCell<Vec<(usize, WarningKind)>>>,
+    /// Set by `parse_into`: buffers to reuse instead of allocating.
+    pub(crate) pool: Option<RefCell<Pool>>,
+    /// Set by `parse_with_deepest_path`: the depth and pointer of the deepest leaf so far.
+    pub(crate) deepest: Option<RefCell<(usize, String)>>,
+    /// Set by `parse_json_with_comments`.
+    pub(crate) comments: Option<RefCell<CommentCollector>>,
+    /// Set by `parse_json_with_trivia_stats`.
+    pub(crate) trivia: Option<RefCell<TriviaStats>>,
+}
+
+impl<'o> ParseState<'o> {
+    pub(crate) fn new(options: &'o ParserOptions) -> Self {
+        Self {
+            options,
+            nodes: Cell::new(0),
+            steps: Cell::new(0),
+            compact: false,
+            recovering: false,
+            errors: RefCell::new(Vec::new()),
+            error_pointer: RefCell::new(None),
+            rejection: RefCell::new(None),
+            truncated: Cell::new(false),
+            stats: None,
+            spans: None,
+            warnings: None,
+            pool: None,
+            deepest: None,
+            comments: None,
+            trivia: None,
+        }
+    }
+
+    pub(crate) fn new_string(&self) -> String {
+        self.pool.as_ref().map_or_else(String::new, |pool| pool.borrow_mut().string())
+    }
+
+    pub(crate) fn new_array(&self) -> Vec<JsonValue> {
+        self.pool.as_ref().map_or_else(Vec::new, |pool| pool.borrow_mut().array())
+    }
+
+    pub(crate) fn new_object(&self) -> Map<String, JsonValue> {
+        self.pool.as_ref().map_or_else(Map::new, |pool| pool.borrow_mut().object())
+    }
+
+    /// Records one more value, returning `false` once `max_nodes` is exceeded.
+    pub(crate) fn add_node(&self) -> bool {
+        let nodes = self.nodes.get() + 1;
+        self.nodes.set(nodes);
+        self.options.max_nodes.is_none_or(|max| nodes <= max)
+    }
+
+    /// Whether a container may open with `open` containers already around it: its contents
+    /// sit two levels below them.
+    pub(crate) fn can_nest(&self, open: usize) -> bool {
+        self.options.max_depth.is_none_or(|max| open + 2 <= max)
+    }
+
+    /// Counts one more step, returning `false` once `max_steps` is exceeded.
+    pub(crate) fn add_step(&self) -> bool {
+        let steps = self.steps.get() + 1;
+        self.steps.set(steps);
+        !self.out_of_steps()
+    }
+
+    pub(crate) fn out_of_steps(&self) -> bool {
+        self.options.max_steps.is_some_and(|max| self.steps.get() > max)
+    }
+
+    /// Notes the container a failing parse stopped in, unless an inner failure already did.
+    pub(crate) fn fail_in(&self, container: impl FnOnce() -> String) {
+        self.error_pointer.borrow_mut().get_or_insert_with(container);
+    }
+
+    /// Records a warning, if they're being collected.
+    pub(crate) fn warn(&self, remaining: usize, kind: WarningKind) {
+        if let Some(warnings) = &self.warnings {
+            warnings.borrow_mut().push((remaining, kind));
+        }
+    }
+
+    /// Records an error for recovery mode, returning `false` and marking the parse truncated
+    /// if `max_errors` errors were already collected.
+    pub(crate) fn record_error(&self, remaining: usize, container: Option<String>, e: ErrMode<ContextError>) -> bool {
+        let mut errors = self.errors.borrow_mut();
+        let message = error_message(e, self.rejection.take());
+        if self.options.max_errors.is_some_and(|max| errors.len() >= max) {
+            self.truncated.set(true);
+            return false;
+        }
+        errors.push((remaining, container, message));
+        true
+    }
+}
@@ -0,0 +1,271 @@
+use crate::pointer::{escape, index};
+use crate::{JsonError, JsonValue, ObjectBuilder};
+use crate::prelude::*;
+
+impl JsonValue {
+    /// Applies an RFC 6902 JSON Patch, an array of `add`, `remove`, `replace`, `move`, `copy`
+    /// and `test` operations.
+    ///
+    /// The patch is atomic: if any operation fails, `self` is left untouched.
+    pub fn apply_patch(&mut self, patch: &JsonValue) -> Result<(), JsonError> {
+        let JsonValue::Array(ops) = patch else {
+            return Err(JsonError::InvalidPatch("patch must be an array".to_string()));
+        };
+
+        let mut doc = self.clone();
+        for op in ops {
+            doc.apply_operation(op)?;
+        }
+        *self = doc;
+        Ok(())
+    }
+
+    /// An RFC 6902 JSON Patch that turns `self` into `target` when passed to `apply_patch`.
+    ///
+    /// Objects and arrays are compared member by member, so only what changed is replaced.
+    /// Arrays are matched by index: surplus elements are removed from the end and missing ones
+    /// appended, without detecting moved elements.
+    pub fn diff_patch(&self, target: &JsonValue) -> JsonValue {
+        let mut ops = Vec::new();
+        diff(&mut String::new(), self, target, &mut ops);
+        JsonValue::Array(ops)
+    }
+
+    /// The top-level keys, sorted, whose values differ between two objects, including keys
+    /// only one of them has; `None` unless both are objects. Nested changes aren't broken down:
+    /// a key is listed if anything below it differs.
+    pub fn changed_keys(&self, other: &JsonValue) -> Option<Vec<String>> {
+        let (old, new) = (self.as_object()?, other.as_object()?);
+        let mut keys: Vec<String> = old.keys()
+            .chain(new.keys().filter(|k| !old.contains_key(*k)))
+            .filter(|k| old.get(*k) != new.get(*k))
+            .cloned()
+            .collect();
+        keys.sort();
+        Some(keys)
+    }
+
+    fn apply_operation(&mut self, op: &JsonValue) -> Result<(), JsonError> {
+        let field = |name: &str| {
+            op.pointer(&format!("/{}", name))
+                .and_then(JsonValue::as_str)
+                .ok_or_else(|| JsonError::InvalidPatch(format!("operation is missing `{}`", name)))
+        };
+        let value = || {
+            op.pointer("/value")
+                .cloned()
+                .ok_or_else(|| JsonError::InvalidPatch("operation is missing `value`".to_string()))
+        };
+        let path = field("path")?;
+
+        match field("op")? {
+            "add" => self.patch_add(path, value()?),
+            "remove" => self.remove(path).map(drop).ok_or_else(|| JsonError::PathNotFound(path.to_string())),
+            "replace" => {
+                let target = self.pointer_mut(path).ok_or_else(|| JsonError::PathNotFound(path.to_string()))?;
+                *target = value()?;
+                Ok(())
+            }
+            "move" => {
+                let from = field("from")?;
+                if path.starts_with(&format!("{}/", from)) {
+                    return Err(JsonError::InvalidPatch(format!("cannot move `{}` into its own child", from)));
+                }
+                let moved = self.remove(from).ok_or_else(|| JsonError::PathNotFound(from.to_string()))?;
+                self.patch_add(path, moved)
+            }
+            "copy" => {
+                let from = field("from")?;
+                let copied = self.pointer(from).cloned().ok_or_else(|| JsonError::PathNotFound(from.to_string()))?;
+                self.patch_add(path, copied)
+            }
+            "test" => match self.pointer(path) {
+                Some(actual) if *actual == value()? => Ok(()),
+                _ => Err(JsonError::TestFailed(path.to_string())),
+            },
+            other => Err(JsonError::InvalidPatch(format!("unknown operation `{}`", other))),
+        }
+    }
+
+    /// The patch `add`: inserts into arrays (`-` appends) and adds or replaces object keys.
+    fn patch_add(&mut self, pointer: &str, value: JsonValue) -> Result<(), JsonError> {
+        if pointer.is_empty() {
+            *self = value;
+            return Ok(());
+        }
+
+        let (parent, last) = pointer.rsplit_once('/').ok_or_else(|| JsonError::InvalidPointer(pointer.to_string()))?;
+        let last = last.replace("~1", "/").replace("~0", "~");
+
+        match self.pointer_mut(parent).ok_or_else(|| JsonError::PathNotFound(pointer.to_string()))? {
+            JsonValue::Object(map) => {
+                map.insert(last, value);
+            }
+            JsonValue::Array(arr) => {
+                let i = if last == "-" { Some(arr.len()) } else { index(&last) };
+                match i {
+                    Some(i) if i <= arr.len() => arr.insert(i, value),
+                    _ => return Err(JsonError::IndexOutOfRange(pointer.to_string())),
+                }
+            }
+            _ => return Err(JsonError::NotAContainer(pointer.to_string())),
+        }
+        Ok(())
+    }
+}
+
+/// Appends to `ops` the operations turning `from` into `to`, both found at `path`.
+fn diff(path: &mut String, from: &JsonValue, to: &JsonValue, ops: &mut Vec<JsonValue>) {
+    if from == to {
+        return;
+    }
+    let len = path.len();
+    match (from, to) {
+        (JsonValue::Object(old), JsonValue::Object(new)) => {
+            let mut keys: Vec<_> = old.keys().chain(new.keys().filter(|k| !old.contains_key(*k))).collect();
+            keys.sort();
+            for key in keys {
+                path.push('/');
+                path.push_str(&escape(key));
+                match (old.get(key), new.get(key)) {
+                    (Some(a), Some(b)) => diff(path, a, b, ops),
+                    (Some(_), None) => ops.push(operation("remove", path, None)),
+                    (None, Some(b)) => ops.push(operation("add", path, Some(b.clone()))),
+                    (None, None) => unreachable!("key comes from one of the objects"),
+                }
+                path.truncate(len);
+            }
+        }
+        (JsonValue::Array(old), JsonValue::Array(new)) => {
+            for (i, (a, b)) in old.iter().zip(new).enumerate() {
+                path.push_str(&format!("/{}", i));
+                diff(path, a, b, ops);
+                path.truncate(len);
+            }
+            for i in (new.len()..old.len()).rev() {
+                ops.push(operation("remove", &format!("{}/{}", path, i), None));
+            }
+            for b in new.iter().skip(old.len()) {
+                ops.push(operation("add", &format!("{}/-", path), Some(b.clone())));
+            }
+        }
+        _ => ops.push(operation("replace", path, Some(to.clone()))),
+    }
+}
+
+fn operation(op: &str, path: &str, value: Option<JsonValue>) -> JsonValue {
+    let builder = ObjectBuilder::new()
+        .key("op", JsonValue::String(op.to_string()))
+        .key("path", JsonValue::String(path.to_string()));
+    match value {
+        Some(value) => builder.key("value", value).build(),
+        None => builder.build(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_json;
+
+    fn doc() -> JsonValue {
+        let input = r#"{"a": {"b": 1}, "list": [1, 2, 3]}"#;
+        parse_json(&mut (&*input)).unwrap()
+    }
+
+    fn patch(input: &str) -> JsonValue {
+        parse_json(&mut (&*input)).unwrap()
+    }
+
+    #[test]
+    fn patch_add_should_work() {
+        let mut json = doc();
+        json.apply_patch(&patch(r#"[
+            {"op": "add", "path": "/a/c", "value": "x"},
+            {"op": "add", "path": "/list/1", "value": 9},
+            {"op": "add", "path": "/list/-", "value": 10}
+        ]"#)).unwrap();
+        assert_eq!(json, patch(r#"{"a": {"b": 1, "c": "x"}, "list": [1, 9, 2, 3, 10]}"#));
+    }
+
+    #[test]
+    fn patch_remove_should_work() {
+        let mut json = doc();
+        json.apply_patch(&patch(r#"[{"op": "remove", "path": "/a/b"}, {"op": "remove", "path": "/list/0"}]"#)).unwrap();
+        assert_eq!(json, patch(r#"{"a": {}, "list": [2, 3]}"#));
+
+        let ret = json.apply_patch(&patch(r#"[{"op": "remove", "path": "/missing"}]"#));
+        assert_eq!(ret, Err(JsonError::PathNotFound("/missing".to_string())));
+    }
+
+    #[test]
+    fn patch_replace_should_work() {
+        let mut json = doc();
+        json.apply_patch(&patch(r#"[{"op": "replace", "path": "/a/b", "value": [true]}]"#)).unwrap();
+        assert_eq!(json, patch(r#"{"a": {"b": [true]}, "list": [1, 2, 3]}"#));
+
+        let ret = json.apply_patch(&patch(r#"[{"op": "replace", "path": "/a/c", "value": 1}]"#));
+        assert_eq!(ret, Err(JsonError::PathNotFound("/a/c".to_string())));
+    }
+
+    #[test]
+    fn patch_move_should_work() {
+        let mut json = doc();
+        json.apply_patch(&patch(r#"[{"op": "move", "from": "/a/b", "path": "/list/0"}]"#)).unwrap();
+        assert_eq!(json, patch(r#"{"a": {}, "list": [1, 1, 2, 3]}"#));
+
+        let ret = json.apply_patch(&patch(r#"[{"op": "move", "from": "/a", "path": "/a/x"}]"#));
+        assert!(matches!(ret, Err(JsonError::InvalidPatch(_))));
+    }
+
+    #[test]
+    fn patch_copy_should_work() {
+        let mut json = doc();
+        json.apply_patch(&patch(r#"[{"op": "copy", "from": "/list", "path": "/a/list"}]"#)).unwrap();
+        assert_eq!(json, patch(r#"{"a": {"b": 1, "list": [1, 2, 3]}, "list": [1, 2, 3]}"#));
+    }
+
+    #[test]
+    fn patch_test_should_work() {
+        let mut json = doc();
+        json.apply_patch(&patch(r#"[{"op": "test", "path": "/a/b", "value": 1}]"#)).unwrap();
+
+        let ret = json.apply_patch(&patch(r#"[
+            {"op": "remove", "path": "/list"},
+            {"op": "test", "path": "/a/b", "value": 2}
+        ]"#));
+        assert_eq!(ret, Err(JsonError::TestFailed("/a/b".to_string())));
+        assert_eq!(json, doc());
+    }
+
+    #[test]
+    fn diff_patch_should_round_trip() {
+        let from = patch(r#"{"a": {"b": 1, "c": [1, 2, 3]}, "d/e": "x", "gone": null, "list": [1, {"k": 1}]}"#);
+        let to = patch(r#"{"a": {"b": 2, "c": [1]}, "d/e": "y", "new": {"n": true}, "list": [0, {"k": 1, "j": 2}, 3, 4]}"#);
+
+        let ops = from.diff_patch(&to);
+        let mut json = from.clone();
+        json.apply_patch(&ops).unwrap();
+        assert_eq!(json, to);
+
+        assert_eq!(ops.pointer("/0"), Some(&patch(r#"{"op": "replace", "path": "/a/b", "value": 2}"#)));
+        assert!(ops.as_array().unwrap().iter().any(|op| op.get_str("/path") == Some("/d~1e")));
+
+        assert_eq!(to.diff_patch(&to), patch("[]"));
+        let mut json = from.clone();
+        json.apply_patch(&from.diff_patch(&patch("[1]"))).unwrap();
+        assert_eq!(json, patch("[1]"));
+    }
+
+    #[test]
+    fn changed_keys_should_work() {
+        let a = patch(r#"{"host": "a", "port": 80, "tls": {"on": true}, "old": 1}"#);
+        let b = patch(r#"{"host": "a", "port": 8080, "tls": {"on": true}, "new": 1}"#);
+        assert_eq!(a.changed_keys(&b), Some(vec!["new".to_string(), "old".to_string(), "port".to_string()]));
+
+        let c = patch(r#"{"host": "b", "port": 80, "tls": {"on": false}, "old": 1}"#);
+        assert_eq!(a.changed_keys(&c), Some(vec!["host".to_string(), "tls".to_string()]));
+        assert_eq!(a.changed_keys(&a), Some(vec![]));
+        assert_eq!(a.changed_keys(&patch("[]")), None);
+    }
+}
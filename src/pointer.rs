@@ -0,0 +1,238 @@
+use crate::{JsonError, JsonValue};
+use crate::prelude::*;
+
+/// Splits an RFC 6901 JSON Pointer into its unescaped reference tokens.
+///
+/// Returns `None` when a non-empty pointer doesn't start with `/`.
+pub(crate) fn tokens(pointer: &str) -> Option<Vec<String>> {
+    if pointer.is_empty() {
+        return Some(Vec::new());
+    }
+
+    let rest = pointer.strip_prefix('/')?;
+    Some(rest.split('/').map(|t| t.replace("~1", "/").replace("~0", "~")).collect())
+}
+
+/// Escapes a key for use as a reference token, the inverse of the unescaping in `tokens`.
+pub(crate) fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Parses an array index token, rejecting leading zeros and signs as RFC 6901 requires.
+pub(crate) fn index(token: &str) -> Option<usize> {
+    if token.is_empty() || (token.len() > 1 && token.starts_with('0')) || !token.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    token.parse().ok()
+}
+
+impl JsonValue {
+    /// Looks up a value by JSON Pointer, e.g. `/address/city` or `/marks/0`.
+    pub fn pointer(&self, pointer: &str) -> Option<&JsonValue> {
+        tokens(pointer)?.iter().try_fold(self, |target, token| match target {
+            JsonValue::Object(map) => map.get(token),
+            JsonValue::Array(arr) => arr.get(index(token)?),
+            _ => None,
+        })
+    }
+
+    /// Whether `pointer` resolves to a value. A present `null` exists; only a missing path or
+    /// malformed pointer returns `false`.
+    pub fn pointer_exists(&self, pointer: &str) -> bool {
+        self.pointer(pointer).is_some()
+    }
+
+    /// Clones the sub-document at `pointer` so it can outlive `self`.
+    pub fn extract(&self, pointer: &str) -> Option<JsonValue> {
+        self.pointer(pointer).cloned()
+    }
+
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut JsonValue> {
+        tokens(pointer)?.iter().try_fold(self, |target, token| match target {
+            JsonValue::Object(map) => map.get_mut(token),
+            JsonValue::Array(arr) => arr.get_mut(index(token)?),
+            _ => None,
+        })
+    }
+
+    /// Deletes and returns the value at `pointer`, shifting later array elements down.
+    ///
+    /// The whole document (`""`) can't be removed.
+    pub fn remove(&mut self, pointer: &str) -> Option<JsonValue> {
+        let (parent, last) = pointer.rsplit_once('/')?;
+        let last = last.replace("~1", "/").replace("~0", "~");
+
+        match self.pointer_mut(parent)? {
+            JsonValue::Object(map) => map.remove(&last),
+            JsonValue::Array(arr) => {
+                let i = index(&last)?;
+                (i < arr.len()).then(|| arr.remove(i))
+            }
+            _ => None,
+        }
+    }
+
+    /// Replaces the value at `pointer`, see `set_with`.
+    pub fn set(&mut self, pointer: &str, value: JsonValue) -> Result<(), JsonError> {
+        self.set_with(pointer, value, false)
+    }
+
+    /// Replaces the value at `pointer`, or adds it when the final token names a new object key.
+    ///
+    /// With `create_missing`, absent intermediate keys are filled in with empty objects.
+    /// Array indices must already exist.
+    pub fn set_with(&mut self, pointer: &str, value: JsonValue, create_missing: bool) -> Result<(), JsonError> {
+        let tokens = tokens(pointer).ok_or_else(|| JsonError::InvalidPointer(pointer.to_string()))?;
+        let Some((last, parents)) = tokens.split_last() else {
+            *self = value;
+            return Ok(());
+        };
+
+        let mut target = self;
+        for token in parents {
+            target = match target {
+                JsonValue::Object(map) => {
+                    if create_missing && !map.contains_key(token) {
+                        map.insert(token.clone(), JsonValue::Object(Default::default()));
+                    }
+                    map.get_mut(token).ok_or_else(|| JsonError::PathNotFound(pointer.to_string()))?
+                }
+                JsonValue::Array(arr) => index(token)
+                    .and_then(|i| arr.get_mut(i))
+                    .ok_or_else(|| JsonError::IndexOutOfRange(pointer.to_string()))?,
+                _ => return Err(JsonError::NotAContainer(pointer.to_string())),
+            };
+        }
+
+        match target {
+            JsonValue::Object(map) => {
+                map.insert(last.clone(), value);
+            }
+            JsonValue::Array(arr) => {
+                let slot = index(last)
+                    .and_then(|i| arr.get_mut(i))
+                    .ok_or_else(|| JsonError::IndexOutOfRange(pointer.to_string()))?;
+                *slot = value;
+            }
+            _ => return Err(JsonError::NotAContainer(pointer.to_string())),
+        }
+        Ok(())
+    }
+
+    pub fn get_str(&self, pointer: &str) -> Option<&str> {
+        self.pointer(pointer)?.as_str()
+    }
+
+    pub fn get_f64(&self, pointer: &str) -> Option<f64> {
+        self.pointer(pointer)?.as_f64()
+    }
+
+    pub fn get_bool(&self, pointer: &str) -> Option<bool> {
+        self.pointer(pointer)?.as_bool()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::sample;
+
+    #[test]
+    fn pointer_should_work() {
+        let json = sample();
+        assert_eq!(json.pointer(""), Some(&json));
+        assert_eq!(json.pointer("/marks/1"), Some(&JsonValue::Number(-80.0)));
+        assert_eq!(json.pointer("/nested/different_element_array/4/s"), Some(&JsonValue::String("str".to_string())));
+        assert_eq!(json.pointer("/marks/01"), None);
+        assert_eq!(json.pointer("/missing"), None);
+        assert_eq!(json.pointer("name"), None);
+    }
+
+    #[test]
+    fn pointer_exists_should_work() {
+        let json = crate::ObjectBuilder::new().key("a", JsonValue::Null).build();
+        assert!(json.pointer_exists("/a"));
+        assert!(!json.pointer_exists("/b"));
+        assert!(json.pointer_exists(""));
+        assert!(!json.pointer_exists("a"));
+
+        let json = sample();
+        assert!(json.pointer_exists("/nested/different_element_array/1"));
+        assert!(!json.pointer_exists("/nested/different_element_array/5"));
+    }
+
+    #[test]
+    fn extract_should_work() {
+        let address = {
+            let json = sample();
+            json.extract("/address").unwrap()
+        };
+        let expected = crate::ObjectBuilder::new()
+            .key("city", JsonValue::String("New York".to_string()))
+            .key("zip", JsonValue::Number(10001.0))
+            .build();
+        assert_eq!(address, expected);
+        assert_eq!(sample().extract("/address/street"), None);
+    }
+
+    #[test]
+    fn get_str_should_work() {
+        let json = sample();
+        assert_eq!(json.get_str("/address/city"), Some("New York"));
+        assert_eq!(json.get_str("/address/zip"), None);
+        assert_eq!(json.get_str("/address/street"), None);
+    }
+
+    #[test]
+    fn get_f64_should_work() {
+        let json = sample();
+        assert_eq!(json.get_f64("/address/zip"), Some(10001.0));
+        assert_eq!(json.get_f64("/marks/2"), Some(85.1));
+        assert_eq!(json.get_f64("/name"), None);
+    }
+
+    #[test]
+    fn get_bool_should_work() {
+        let json = sample();
+        assert_eq!(json.get_bool("/is_student"), Some(false));
+        assert_eq!(json.get_bool("/nested/different_element_array/2"), Some(true));
+        assert_eq!(json.get_bool("/age"), None);
+    }
+
+    #[test]
+    fn set_should_work() {
+        let mut json = sample();
+        json.set("/address/city", JsonValue::String("Boston".to_string())).unwrap();
+        assert_eq!(json.get_str("/address/city"), Some("Boston"));
+
+        json.set("/marks/0", JsonValue::Null).unwrap();
+        assert_eq!(json.pointer("/marks/0"), Some(&JsonValue::Null));
+
+        json.set_with("/a/b/c", JsonValue::Boolean(true), true).unwrap();
+        assert_eq!(json.get_bool("/a/b/c"), Some(true));
+    }
+
+    #[test]
+    fn set_should_fail_on_invalid_path() {
+        let mut json = sample();
+        let value = JsonValue::Null;
+        assert_eq!(json.set("address", value.clone()), Err(JsonError::InvalidPointer("address".to_string())));
+        assert_eq!(json.set("/x/y", value.clone()), Err(JsonError::PathNotFound("/x/y".to_string())));
+        assert_eq!(json.set("/marks/3", value.clone()), Err(JsonError::IndexOutOfRange("/marks/3".to_string())));
+        assert_eq!(json.set("/name/first", value), Err(JsonError::NotAContainer("/name/first".to_string())));
+    }
+
+    #[test]
+    fn remove_should_work() {
+        let mut json = sample();
+        assert_eq!(json.remove("/address/city"), Some(JsonValue::String("New York".to_string())));
+        assert_eq!(json.pointer("/address"), Some(&crate::ObjectBuilder::new().key("zip", JsonValue::Number(10001.0)).build()));
+
+        assert_eq!(json.remove("/marks/0"), Some(JsonValue::Number(90.0)));
+        assert_eq!(json.pointer("/marks"), Some(&JsonValue::Array(vec![JsonValue::Number(-80.0), JsonValue::Number(85.1)])));
+
+        assert_eq!(json.remove("/marks/2"), None);
+        assert_eq!(json.remove("/address/city"), None);
+        assert_eq!(json.remove(""), None);
+    }
+}
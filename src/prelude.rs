@@ -0,0 +1,79 @@
+//! The `alloc` names the `std` prelude would otherwise bring into scope, plus stand-ins for
+//! the `f64` and `HashMap` methods that only exist with `std`.
+pub(crate) use alloc::format;
+pub(crate) use alloc::string::{String, ToString};
+pub(crate) use alloc::vec;
+pub(crate) use alloc::vec::Vec;
+
+#[cfg(not(feature = "std"))]
+pub(crate) trait FloatExt {
+    fn powi(self, n: i32) -> f64;
+    fn fract(self) -> f64;
+    fn round(self) -> f64;
+}
+
+#[cfg(not(feature = "std"))]
+impl FloatExt for f64 {
+    /// Square-and-multiply, the same steps `std` takes through `__powidf2`.
+    fn powi(self, n: i32) -> f64 {
+        let (mut base, mut exp, mut ret) = (self, n.unsigned_abs(), 1.0);
+        while exp > 0 {
+            if exp & 1 == 1 {
+                ret *= base;
+            }
+            base *= base;
+            exp >>= 1;
+        }
+        if n < 0 { 1.0 / ret } else { ret }
+    }
+
+    fn fract(self) -> f64 {
+        self - (self - self % 1.0).copysign(self)
+    }
+
+    /// Rounds half away from zero, like `f64::round`.
+    fn round(self) -> f64 {
+        let fract = self % 1.0;
+        let trunc = (self - fract).copysign(self);
+        if fract >= 0.5 {
+            trunc + 1.0
+        } else if fract <= -0.5 {
+            trunc - 1.0
+        } else {
+            trunc
+        }
+    }
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) trait MapExt<K, V> {
+    fn drain(&mut self) -> alloc::collections::btree_map::IntoIter<K, V>;
+    fn shrink_to_fit(&mut self);
+}
+
+/// A `BTreeMap` has no spare capacity, so draining just takes the map and shrinking is a no-op.
+#[cfg(not(feature = "std"))]
+impl<K, V> MapExt<K, V> for alloc::collections::BTreeMap<K, V> {
+    fn drain(&mut self) -> alloc::collections::btree_map::IntoIter<K, V> {
+        core::mem::take(self).into_iter()
+    }
+
+    fn shrink_to_fit(&mut self) {}
+}
+
+/// Test builds link `std` even without the feature, so the stand-ins can be checked against it.
+#[cfg(all(test, not(feature = "std")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn float_ext_should_match_std() {
+        for n in -330..330 {
+            assert_eq!(FloatExt::powi(10.0, n).to_bits(), 10_f64.powi(n).to_bits(), "{}", n);
+        }
+        for x in [0.0, -0.0, 0.3, -0.3, 0.5, -0.5, 2.5, -2.5, 3.7, -3.7, 0.49999999999999994, 4503599627370495.5, 1e300] {
+            assert_eq!(FloatExt::round(x).to_bits(), x.round().to_bits(), "{}", x);
+            assert_eq!(FloatExt::fract(x).to_bits(), x.fract().to_bits(), "{}", x);
+        }
+    }
+}
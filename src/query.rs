@@ -0,0 +1,346 @@
+use core::ops::ControlFlow;
+use crate::ser::write_string;
+use crate::{JsonKind, JsonValue, Map};
+use crate::prelude::*;
+
+impl JsonValue {
+    /// Collects every string value in the tree, depth-first; see `string_leaves_with`.
+    pub fn string_leaves(&self) -> Vec<&str> {
+        self.string_leaves_with(false)
+    }
+
+    /// Collects every string value in the tree, depth-first, plus object keys when
+    /// `include_keys` is set.
+    pub fn string_leaves_with(&self, include_keys: bool) -> Vec<&str> {
+        let mut leaves = Vec::new();
+        self.collect_strings(include_keys, &mut leaves);
+        leaves
+    }
+
+    fn collect_strings<'a>(&'a self, include_keys: bool, leaves: &mut Vec<&'a str>) {
+        match self {
+            JsonValue::String(s) => leaves.push(s),
+            JsonValue::Array(arr) => arr.iter().for_each(|v| v.collect_strings(include_keys, leaves)),
+            JsonValue::Object(map) => {
+                for (k, v) in map {
+                    if include_keys {
+                        leaves.push(k);
+                    }
+                    v.collect_strings(include_keys, leaves);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Whether `needle` is this value or equal to any value nested inside it.
+    pub fn deep_contains(&self, needle: &JsonValue) -> bool {
+        if self == needle {
+            return true;
+        }
+        match self {
+            JsonValue::Array(arr) => arr.iter().any(|v| v.deep_contains(needle)),
+            JsonValue::Object(map) => map.values().any(|v| v.deep_contains(needle)),
+            _ => false,
+        }
+    }
+
+    /// Whether any string value in the tree contains `substr`. Object keys aren't searched.
+    pub fn deep_contains_str(&self, substr: &str) -> bool {
+        match self {
+            JsonValue::String(s) => s.contains(substr),
+            JsonValue::Array(arr) => arr.iter().any(|v| v.deep_contains_str(substr)),
+            JsonValue::Object(map) => map.values().any(|v| v.deep_contains_str(substr)),
+            _ => false,
+        }
+    }
+
+    /// The deepest nesting level in the tree: a scalar (or empty container) is 1 and each
+    /// enclosing container adds one.
+    pub fn max_depth(&self) -> usize {
+        // An explicit stack rather than recursion, so any tree the parser accepts can be measured.
+        let mut max = 0;
+        let mut pending = vec![(self, 1)];
+        while let Some((value, depth)) = pending.pop() {
+            max = max.max(depth);
+            match value {
+                JsonValue::Array(arr) => pending.extend(arr.iter().map(|v| (v, depth + 1))),
+                JsonValue::Object(map) => pending.extend(map.values().map(|v| (v, depth + 1))),
+                _ => {}
+            }
+        }
+        max
+    }
+
+    /// Groups an array's elements by their value for `key`, see `group_by_with`. Elements
+    /// without the key are skipped.
+    pub fn group_by(&self, key: &str) -> Option<Map<String, Vec<&JsonValue>>> {
+        self.group_by_with(key, None)
+    }
+
+    /// Groups an array's elements by their value for `key`: strings by their contents and other
+    /// values by their JSON text. Elements that aren't objects or lack the key go under the
+    /// `missing` bucket, or are skipped if it's `None`. Returns `None` for non-arrays.
+    pub fn group_by_with(&self, key: &str, missing: Option<&str>) -> Option<Map<String, Vec<&JsonValue>>> {
+        let mut groups: Map<String, Vec<&JsonValue>> = Map::new();
+        for element in self.as_array()? {
+            let group = match element.as_object().and_then(|map| map.get(key)) {
+                Some(JsonValue::String(s)) => s.clone(),
+                Some(value) => value.to_string(),
+                None => match missing {
+                    Some(bucket) => bucket.to_string(),
+                    None => continue,
+                },
+            };
+            groups.entry(group).or_default().push(element);
+        }
+        Some(groups)
+    }
+
+    /// Visits every node, containers before their contents, with its JSON Pointer, stopping as
+    /// soon as `visitor` breaks; returns whether it did. Arrays are visited in order and objects
+    /// in sorted key order.
+    pub fn walk(&self, mut visitor: impl FnMut(&str, &JsonValue) -> ControlFlow<()>) -> ControlFlow<()> {
+        self.walk_from(&mut String::new(), &mut visitor)
+    }
+
+    fn walk_from(&self, pointer: &mut String, visitor: &mut impl FnMut(&str, &JsonValue) -> ControlFlow<()>) -> ControlFlow<()> {
+        visitor(pointer, self)?;
+        let len = pointer.len();
+        match self {
+            JsonValue::Array(arr) => {
+                for (i, v) in arr.iter().enumerate() {
+                    pointer.push_str(&format!("/{}", i));
+                    v.walk_from(pointer, visitor)?;
+                    pointer.truncate(len);
+                }
+            }
+            JsonValue::Object(_) => {
+                for (k, v) in self.sorted_entries().unwrap_or_default() {
+                    pointer.push('/');
+                    pointer.push_str(&crate::pointer::escape(k));
+                    v.walk_from(pointer, visitor)?;
+                    pointer.truncate(len);
+                }
+            }
+            _ => {}
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// Counts the scalar leaves in the tree; containers, even empty ones, aren't counted.
+    pub fn scalar_count(&self) -> usize {
+        match self {
+            JsonValue::Array(arr) => arr.iter().map(JsonValue::scalar_count).sum(),
+            JsonValue::Object(map) => map.values().map(JsonValue::scalar_count).sum(),
+            _ => 1,
+        }
+    }
+
+    /// Splits the length of the compact serialization (`to_string`) by value kind, to see what
+    /// makes a payload large. Each scalar counts its own JSON text, quotes and escapes included;
+    /// brackets, commas, and object keys with their quotes and `:` count towards the container
+    /// they belong to. The totals add up to `to_string().len()`; kinds that don't occur are absent.
+    pub fn bytes_by_kind(&self) -> Map<JsonKind, usize> {
+        let mut bytes = Map::new();
+        self.count_bytes(&mut bytes);
+        bytes
+    }
+
+    fn count_bytes(&self, bytes: &mut Map<JsonKind, usize>) {
+        let own = match self {
+            JsonValue::Array(arr) => {
+                arr.iter().for_each(|v| v.count_bytes(bytes));
+                2 + arr.len().saturating_sub(1)
+            }
+            JsonValue::Object(map) => {
+                let mut key = String::new();
+                let keys: usize = map.keys()
+                    .map(|k| {
+                        key.clear();
+                        write_string(&mut key, k).expect("writing to a String can't fail");
+                        key.len() + 1
+                    })
+                    .sum();
+                map.values().for_each(|v| v.count_bytes(bytes));
+                2 + keys + map.len().saturating_sub(1)
+            }
+            _ => self.to_string().len(),
+        };
+        *bytes.entry(self.kind()).or_default() += own;
+    }
+
+    /// Flattens the tree into `.env`-style entries: dotted paths such as `address.city` or
+    /// `marks.0` mapped to the scalar at that path.
+    ///
+    /// Strings map to their raw contents; other scalars and empty containers use their JSON text.
+    /// A `.` or `\` inside an object key is escaped with a backslash, so `{"a.b": 1}` flattens to
+    /// `a\.b` and can't collide with `{"a": {"b": 1}}`.
+    pub fn to_flat_config(&self) -> Map<String, String> {
+        let mut config = Map::new();
+        self.flatten_into(String::new(), &mut config);
+        config
+    }
+
+    fn flatten_into(&self, prefix: String, config: &mut Map<String, String>) {
+        let join = |key: &dyn core::fmt::Display| {
+            if prefix.is_empty() { key.to_string() } else { format!("{}.{}", prefix, key) }
+        };
+
+        match self {
+            JsonValue::Array(arr) if !arr.is_empty() => {
+                arr.iter().enumerate().for_each(|(i, v)| v.flatten_into(join(&i), config));
+            }
+            JsonValue::Object(map) if !map.is_empty() => {
+                for (k, v) in map {
+                    let key = k.replace('\\', "\\\\").replace('.', "\\.");
+                    v.flatten_into(join(&key), config);
+                }
+            }
+            JsonValue::String(s) => {
+                config.insert(prefix, s.clone());
+            }
+            _ => {
+                config.insert(prefix, self.to_string());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::collections::BTreeSet;
+    use super::*;
+    use crate::ObjectBuilder;
+    use crate::tests::sample;
+
+    #[test]
+    fn string_leaves_should_work() {
+        let json = sample();
+        let leaves: BTreeSet<&str> = json.string_leaves().into_iter().collect();
+        assert_eq!(leaves, BTreeSet::from(["John Doe", "New York", "hello", "str"]));
+
+        let address = json.pointer("/address").unwrap();
+        let leaves: BTreeSet<&str> = address.string_leaves_with(true).into_iter().collect();
+        assert_eq!(leaves, BTreeSet::from(["city", "New York", "zip"]));
+    }
+
+    #[test]
+    fn to_flat_config_should_work() {
+        let config = sample().to_flat_config();
+        assert_eq!(config["name"], "John Doe");
+        assert_eq!(config["age"], "30");
+        assert_eq!(config["is_student"], "false");
+        assert_eq!(config["marks.1"], "-80");
+        assert_eq!(config["address.city"], "New York");
+        assert_eq!(config["nested.different_element_array.1"], "null");
+        assert_eq!(config["nested.different_element_array.4.s"], "str");
+        assert_eq!(config["nested.empty_arr"], "[]");
+        assert_eq!(config["nested.empty_obj"], "{}");
+        assert!(!config.contains_key("address"));
+
+        let json = crate::parse_json(&mut r#"{"a.b": 1, "a": {"b": 2, "c\\": {"d": 3}}}"#).unwrap();
+        let config = json.to_flat_config();
+        assert_eq!(config.len(), 3);
+        assert_eq!(config[r"a\.b"], "1");
+        assert_eq!(config["a.b"], "2");
+        assert_eq!(config[r"a.c\\.d"], "3");
+    }
+
+    #[test]
+    fn deep_contains_should_work() {
+        let json = sample();
+        let needle = ObjectBuilder::new()
+            .key("a", JsonValue::Number(1.0))
+            .key("s", JsonValue::String("str".to_string()))
+            .build();
+        assert!(json.deep_contains(&needle));
+        assert!(json.deep_contains(&JsonValue::Number(30.0)));
+        assert!(json.deep_contains(&json));
+        assert!(!json.deep_contains(&JsonValue::String("s".to_string())));
+        assert!(!json.deep_contains(&JsonValue::Number(31.0)));
+    }
+
+    #[test]
+    fn deep_contains_str_should_work() {
+        let json = sample();
+        assert!(json.deep_contains_str("York"));
+        assert!(json.deep_contains_str("st"));
+        assert!(!json.deep_contains_str("city"));
+    }
+
+    #[test]
+    fn group_by_should_work() {
+        let input = r#"[
+            {"type": "fruit", "name": "apple"},
+            {"type": "veg", "name": "leek"},
+            {"type": "fruit", "name": "pear"},
+            {"type": 1},
+            {"name": "rock"},
+            "loose"
+        ]"#;
+        let json = crate::parse_json(&mut (&*input)).unwrap();
+
+        let groups = json.group_by("type").unwrap();
+        assert_eq!(groups.len(), 3);
+        let names: Vec<&str> = groups["fruit"].iter().filter_map(|v| v.get_str("/name")).collect();
+        assert_eq!(names, ["apple", "pear"]);
+        assert_eq!(groups["veg"].len(), 1);
+        assert_eq!(groups["1"], [&ObjectBuilder::new().key("type", JsonValue::Number(1.0)).build()]);
+
+        let groups = json.group_by_with("type", Some("other")).unwrap();
+        assert_eq!(groups["other"].len(), 2);
+        assert_eq!(sample().group_by("type"), None);
+    }
+
+    #[test]
+    fn scalar_count_should_work() {
+        let json = sample();
+        assert_eq!(json.scalar_count(), 17);
+        assert_eq!(json.pointer("/marks").unwrap().scalar_count(), 3);
+        assert_eq!(json.pointer("/nested/empty_obj").unwrap().scalar_count(), 0);
+        assert_eq!(JsonValue::Null.scalar_count(), 1);
+    }
+
+    #[test]
+    fn walk_should_stop_early() {
+        let json = sample();
+        let mut visited = Vec::new();
+        let flow = json.walk(|pointer, value| {
+            visited.push(pointer.to_string());
+            if value.as_str() == Some("New York") { ControlFlow::Break(()) } else { ControlFlow::Continue(()) }
+        });
+        assert_eq!(flow, ControlFlow::Break(()));
+        assert_eq!(visited, ["", "/address", "/address/city"]);
+
+        let mut count = 0;
+        assert_eq!(json.walk(|_, _| { count += 1; ControlFlow::Continue(()) }), ControlFlow::Continue(()));
+        assert_eq!(count, 17 + 8);
+    }
+
+    #[test]
+    fn bytes_by_kind_should_work() {
+        let json = crate::parse_json(&mut r#"{"name": "a\"b", "ids": [1, 22, 333], "ok": true, "none": null}"#).unwrap();
+        let bytes = json.bytes_by_kind();
+        assert_eq!(bytes[&JsonKind::String], 6);
+        assert_eq!(bytes[&JsonKind::Number], 6);
+        assert_eq!(bytes[&JsonKind::Boolean], 4);
+        assert_eq!(bytes[&JsonKind::Null], 4);
+        assert_eq!(bytes[&JsonKind::Array], 4);
+        assert_eq!(bytes[&JsonKind::Object], 2 + 7 + 6 + 5 + 7 + 3);
+        assert_eq!(bytes.values().sum::<usize>(), json.to_string().len());
+
+        let bytes = sample().bytes_by_kind();
+        assert_eq!(bytes.values().sum::<usize>(), sample().to_string().len());
+        assert_eq!(JsonValue::Null.bytes_by_kind(), Map::from([(JsonKind::Null, 4)]));
+    }
+
+    #[test]
+    fn max_depth_should_work() {
+        let json = sample();
+        assert_eq!(json.max_depth(), 5);
+        assert_eq!(json.pointer("/marks").unwrap().max_depth(), 2);
+        assert_eq!(json.pointer("/nested/empty_arr").unwrap().max_depth(), 1);
+        assert_eq!(JsonValue::Number(1.0).max_depth(), 1);
+    }
+}
@@ -0,0 +1,113 @@
+use core::cell::RefCell;
+use anyhow::Result;
+use winnow::Parser;
+use winnow::ascii::multispace0;
+use winnow::combinator::{delimited, eof, terminated};
+use winnow::error::{StrContext, StrContextValue};
+use crate::options::ParseState;
+use crate::{expect_value, syntax_error, Input, JsonValue, Map, ParserOptions};
+use crate::prelude::*;
+
+/// Emptied buffers taken apart from a previous tree, handed back out while parsing the next.
+#[derive(Debug, Default)]
+pub(crate) struct Pool {
+    strings: Vec<String>,
+    arrays: Vec<Vec<JsonValue>>,
+    objects: Vec<Map<String, JsonValue>>,
+}
+
+impl Pool {
+    /// Takes `value` apart, keeping every buffer with its capacity. Walks an explicit stack so
+    /// deep trees can't overflow the call stack.
+    fn reclaim(&mut self, value: JsonValue) {
+        let mut pending = vec![value];
+        while let Some(mut value) = pending.pop() {
+            match &mut value {
+                JsonValue::String(s) | JsonValue::RawNumber(s) => {
+                    let mut s = core::mem::take(s);
+                    s.clear();
+                    self.strings.push(s);
+                }
+                JsonValue::Array(arr) => {
+                    let mut arr = core::mem::take(arr);
+                    pending.append(&mut arr);
+                    self.arrays.push(arr);
+                }
+                JsonValue::Object(map) => {
+                    let mut map = core::mem::take(map);
+                    for (mut k, v) in map.drain() {
+                        k.clear();
+                        self.strings.push(k);
+                        pending.push(v);
+                    }
+                    self.objects.push(map);
+                }
+                JsonValue::Number(_) | JsonValue::Integer(_) | JsonValue::Boolean(_) | JsonValue::Null => {}
+            }
+        }
+    }
+
+    pub(crate) fn string(&mut self) -> String {
+        self.strings.pop().unwrap_or_default()
+    }
+
+    pub(crate) fn array(&mut self) -> Vec<JsonValue> {
+        self.arrays.pop().unwrap_or_default()
+    }
+
+    pub(crate) fn object(&mut self) -> Map<String, JsonValue> {
+        self.objects.pop().unwrap_or_default()
+    }
+}
+
+/// Parses like `parse_json` into `out`, reusing the strings, arrays and objects of the value
+/// already there instead of allocating fresh ones where it can. Meant for hot loops that parse
+/// many similar documents; `out` is left `null` on error.
+pub fn parse_into(input: &str, out: &mut JsonValue) -> Result<()> {
+    let mut pool = Pool::default();
+    pool.reclaim(core::mem::replace(out, JsonValue::Null));
+
+    let options = ParserOptions::default();
+    let mut state = ParseState::new(&options);
+    state.pool = Some(RefCell::new(pool));
+    let mut stateful = Input { input, state: &state };
+
+    let end = eof.context(StrContext::Expected(StrContextValue::Description("end of input")));
+    *out = terminated(delimited(multispace0, expect_value, multispace0), end)
+        .parse_next(&mut stateful)
+        .map_err(|e| syntax_error(input, &stateful, e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{sample, SAMPLE};
+
+    #[test]
+    fn parse_into_should_work() {
+        let mut out = JsonValue::Null;
+        parse_into(SAMPLE, &mut out).unwrap();
+        assert_eq!(out, sample());
+
+        let input = r#"{"name": "Jane", "tags": ["a", "b"], "nested": {"x": [1, [2]]}}"#;
+        parse_into(input, &mut out).unwrap();
+        assert_eq!(out, crate::parse_json(&mut (&*input)).unwrap());
+
+        assert!(parse_into("[1, 2", &mut out).is_err());
+        assert_eq!(out, JsonValue::Null);
+    }
+
+    #[test]
+    fn parse_into_should_reuse_buffers() {
+        let mut out = crate::parse_json(&mut r#"["a fairly long string buffer", [1, 2, 3, 4]]"#).unwrap();
+        let JsonValue::Array(arr) = &out else { panic!("expected array") };
+        let (outer, string) = (arr.as_ptr(), arr[0].as_str().unwrap().as_ptr());
+
+        parse_into(r#"[["x"]]"#, &mut out).unwrap();
+        let JsonValue::Array(arr) = &out else { panic!("expected array") };
+        let JsonValue::Array(inner) = &arr[0] else { panic!("expected array") };
+        assert!(arr.as_ptr() == outer || inner.as_ptr() == outer);
+        assert_eq!(inner[0].as_str().unwrap().as_ptr(), string);
+    }
+}
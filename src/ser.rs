@@ -0,0 +1,616 @@
+use core::fmt::{self, Display, Formatter, Write};
+use core::slice;
+#[cfg(feature = "std")]
+use std::collections::hash_map as map;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(not(feature = "std"))]
+use alloc::collections::btree_map as map;
+use crate::JsonValue;
+use crate::prelude::*;
+
+/// Knobs for `JsonValue::to_string_with`; the defaults produce the same output as `Display`.
+#[derive(Debug, Clone, Default)]
+pub struct SerializeOptions {
+    extra_escapes: Vec<char>,
+    skip_empty: bool,
+    trailing_newline: bool,
+}
+
+impl SerializeOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Additionally writes these characters in strings and keys as `\uXXXX` escapes, e.g. to
+    /// embed the output in a CSV cell or shell command.
+    pub fn escape_chars(mut self, chars: impl IntoIterator<Item = char>) -> Self {
+        self.extra_escapes.extend(chars);
+        self
+    }
+
+    /// Leaves out empty arrays and objects nested inside containers: object members holding
+    /// one are omitted along with their key, and array elements are dropped. Containers that
+    /// only become empty this way are still written.
+    pub fn skip_empty(mut self, skip_empty: bool) -> Self {
+        self.skip_empty = skip_empty;
+        self
+    }
+
+    /// Ends the output with `\n`, as many tools expect of files.
+    pub fn trailing_newline(mut self, trailing_newline: bool) -> Self {
+        self.trailing_newline = trailing_newline;
+        self
+    }
+}
+
+/// Layout knobs for `JsonValue::to_pretty_string`.
+#[derive(Debug, Clone)]
+pub struct PrettyConfig {
+    pub(crate) indent: String,
+    pub(crate) space_after_colon: bool,
+    inline_scalar_arrays: bool,
+    max_width: Option<usize>,
+}
+
+impl Default for PrettyConfig {
+    /// Two-space indentation, a space after each `:` and every non-empty container expanded.
+    fn default() -> Self {
+        Self {
+            indent: "  ".to_string(),
+            space_after_colon: true,
+            inline_scalar_arrays: false,
+            max_width: None,
+        }
+    }
+}
+
+impl PrettyConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The string written once per nesting level, e.g. four spaces or `"\t"`.
+    pub fn indent(mut self, indent: impl Into<String>) -> Self {
+        self.indent = indent.into();
+        self
+    }
+
+    pub fn space_after_colon(mut self, space: bool) -> Self {
+        self.space_after_colon = space;
+        self
+    }
+
+    /// Keeps arrays holding only scalars on one line, like `[1, 2, 3]`.
+    pub fn inline_scalar_arrays(mut self, inline: bool) -> Self {
+        self.inline_scalar_arrays = inline;
+        self
+    }
+
+    /// Collapses any container onto one line when it fits within this many characters,
+    /// counting the indentation and key before it.
+    pub fn max_width(mut self, max_width: usize) -> Self {
+        self.max_width = Some(max_width);
+        self
+    }
+}
+
+impl JsonValue {
+    /// Writes the value as indented JSON laid out according to `config`.
+    pub fn to_pretty_string(&self, config: &PrettyConfig) -> String {
+        let mut out = String::new();
+        write_pretty(&mut out, self, config, 0, 0).expect("writing to a String can't fail");
+        out
+    }
+
+    pub fn to_string_with(&self, options: &SerializeOptions) -> String {
+        let mut out = String::new();
+        write_value(&mut out, self, options).expect("writing to a String can't fail");
+        if options.trailing_newline {
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Serializes as compact JSON, like `Display`, but gives up once the output would pass
+    /// `max_bytes` and ends it with `...` instead, e.g. for logging values of unknown size.
+    ///
+    /// A truncated result is at most `max_bytes` long including the marker, or just the marker
+    /// if `max_bytes` is smaller, and is never cut inside an escape or a UTF-8 character.
+    pub fn to_string_truncated(&self, max_bytes: usize) -> String {
+        let mut capped = Capped { out: String::new(), max: max_bytes };
+        if write_value(&mut capped, self, &SerializeOptions::default()).is_ok() {
+            return capped.out;
+        }
+        let mut out = capped.out;
+        out.truncate(safe_cut(&out, max_bytes.saturating_sub(TRUNCATED.len())));
+        out.push_str(TRUNCATED);
+        out
+    }
+
+    /// Serializes as compact JSON, like `Display`, one piece at a time: each call to `next` on
+    /// the returned iterator produces at most `chunk_size` bytes (but at least one character),
+    /// so the output can be fed to a writer that applies backpressure, such as an async socket.
+    pub fn serialize_chunks(&self, chunk_size: usize) -> SerializeChunks<'_> {
+        SerializeChunks { stack: vec![Pending::Value(self)], buf: String::new(), chunk_size: chunk_size.max(1) }
+    }
+
+    /// Writes the value as compact JSON to `writer`, in chunks of up to 8 KiB.
+    #[cfg(feature = "std")]
+    pub fn write_to(&self, writer: &mut impl io::Write) -> io::Result<()> {
+        for chunk in self.serialize_chunks(8 * 1024) {
+            writer.write_all(chunk.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+/// The iterator returned by `JsonValue::serialize_chunks`.
+#[derive(Debug)]
+pub struct SerializeChunks<'a> {
+    stack: Vec<Pending<'a>>,
+    buf: String,
+    chunk_size: usize,
+}
+
+/// Output still to be written, innermost last. Containers carry whether their next entry is
+/// the first, which needs no leading `,`.
+#[derive(Debug)]
+enum Pending<'a> {
+    Value(&'a JsonValue),
+    Elements(slice::Iter<'a, JsonValue>, bool),
+    Members(map::Iter<'a, String, JsonValue>, bool),
+}
+
+impl Iterator for SerializeChunks<'_> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        while self.buf.len() < self.chunk_size {
+            match self.stack.pop() {
+                Some(Pending::Value(JsonValue::Array(arr))) => {
+                    self.buf.push('[');
+                    self.stack.push(Pending::Elements(arr.iter(), true));
+                }
+                Some(Pending::Value(JsonValue::Object(map))) => {
+                    self.buf.push('{');
+                    self.stack.push(Pending::Members(map.iter(), true));
+                }
+                Some(Pending::Value(value)) => {
+                    write_value(&mut self.buf, value, &SerializeOptions::default()).expect("writing to a String can't fail");
+                }
+                Some(Pending::Elements(mut elements, first)) => match elements.next() {
+                    Some(value) => {
+                        if !first {
+                            self.buf.push(',');
+                        }
+                        self.stack.push(Pending::Elements(elements, false));
+                        self.stack.push(Pending::Value(value));
+                    }
+                    None => self.buf.push(']'),
+                },
+                Some(Pending::Members(mut members, first)) => match members.next() {
+                    Some((key, value)) => {
+                        if !first {
+                            self.buf.push(',');
+                        }
+                        write_string(&mut self.buf, key).expect("writing to a String can't fail");
+                        self.buf.push(':');
+                        self.stack.push(Pending::Members(members, false));
+                        self.stack.push(Pending::Value(value));
+                    }
+                    None => self.buf.push('}'),
+                },
+                None => break,
+            }
+        }
+        if self.buf.is_empty() {
+            return None;
+        }
+
+        // A long string can overshoot the limit, so split at the last char boundary within it.
+        let mut end = self.chunk_size.min(self.buf.len());
+        while !self.buf.is_char_boundary(end) {
+            end -= 1;
+        }
+        if end == 0 {
+            end = self.buf.chars().next().map_or(0, char::len_utf8);
+        }
+        let rest = self.buf.split_off(end);
+        Some(core::mem::replace(&mut self.buf, rest))
+    }
+}
+
+/// Appended by `JsonValue::to_string_truncated` when it cuts the output short.
+const TRUNCATED: &str = "...";
+
+/// A writer that fails once more than `max` bytes were written to it.
+struct Capped {
+    out: String,
+    max: usize,
+}
+
+impl Write for Capped {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.out.push_str(s);
+        if self.out.len() > self.max { Err(fmt::Error) } else { Ok(()) }
+    }
+}
+
+/// The last offset at or before `max` where `json` can be cut: a char boundary that isn't
+/// inside an escape sequence.
+fn safe_cut(json: &str, max: usize) -> usize {
+    let mut safe = 0;
+    let mut in_string = false;
+    // Characters still to come in the current escape, after its backslash.
+    let mut escape = 0;
+    for (i, c) in json.char_indices() {
+        if i > max {
+            break;
+        }
+        if escape == 0 {
+            safe = i;
+        }
+        if escape > 0 {
+            escape = if escape == 1 && c == 'u' { 4 } else { escape - 1 };
+        } else if in_string {
+            match c {
+                '\\' => escape = 1,
+                '"' => in_string = false,
+                _ => {}
+            }
+        } else if c == '"' {
+            in_string = true;
+        }
+    }
+    safe
+}
+
+impl Display for JsonValue {
+    /// Writes the value as compact JSON.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_value(f, self, &SerializeOptions::default())
+    }
+}
+
+fn write_value<W: Write>(f: &mut W, value: &JsonValue, options: &SerializeOptions) -> fmt::Result {
+    match value {
+        JsonValue::String(s) => write_escaped(f, s, &options.extra_escapes),
+        JsonValue::Number(n) => write_number(f, *n),
+        JsonValue::Integer(i) => write!(f, "{}", i),
+        JsonValue::RawNumber(s) => f.write_str(s),
+        JsonValue::Boolean(b) => write!(f, "{}", b),
+        JsonValue::Null => f.write_str("null"),
+        JsonValue::Array(arr) => {
+            f.write_char('[')?;
+            let elements = arr.iter().filter(|v| !(options.skip_empty && is_empty_container(v)));
+            for (i, v) in elements.enumerate() {
+                if i > 0 {
+                    f.write_char(',')?;
+                }
+                write_value(f, v, options)?;
+            }
+            f.write_char(']')
+        }
+        JsonValue::Object(map) => {
+            f.write_char('{')?;
+            let members = map.iter().filter(|(_, v)| !(options.skip_empty && is_empty_container(v)));
+            for (i, (k, v)) in members.enumerate() {
+                if i > 0 {
+                    f.write_char(',')?;
+                }
+                write_escaped(f, k, &options.extra_escapes)?;
+                f.write_char(':')?;
+                write_value(f, v, options)?;
+            }
+            f.write_char('}')
+        }
+    }
+}
+
+/// Writes `value` starting `column` characters into a line at nesting level `depth`.
+fn write_pretty(out: &mut String, value: &JsonValue, config: &PrettyConfig, depth: usize, column: usize) -> fmt::Result {
+    let inline_scalars = config.inline_scalar_arrays
+        && value.as_array().is_some_and(|arr| arr.iter().all(|v| !matches!(v, JsonValue::Array(_) | JsonValue::Object(_))));
+    if inline_scalars || is_empty_container(value) || !matches!(value, JsonValue::Array(_) | JsonValue::Object(_)) {
+        return write_inline(out, value, config);
+    }
+    if let Some(max) = config.max_width {
+        let mut inline = String::new();
+        write_inline(&mut inline, value, config)?;
+        if column + inline.chars().count() <= max {
+            out.push_str(&inline);
+            return Ok(());
+        }
+    }
+
+    let (open, close) = if value.as_array().is_some() { ('[', ']') } else { ('{', '}') };
+    out.push(open);
+    let mut first = true;
+    let mut write_member = |out: &mut String, key: Option<&str>, v: &JsonValue| {
+        if !first {
+            out.push(',');
+        }
+        first = false;
+        out.push('\n');
+        let line_start = out.len();
+        out.push_str(&config.indent.repeat(depth + 1));
+        if let Some(key) = key {
+            write_string(out, key)?;
+            out.push(':');
+            if config.space_after_colon {
+                out.push(' ');
+            }
+        }
+        let column = out[line_start..].chars().count();
+        write_pretty(out, v, config, depth + 1, column)
+    };
+    match value {
+        JsonValue::Array(arr) => arr.iter().try_for_each(|v| write_member(out, None, v))?,
+        JsonValue::Object(map) => map.iter().try_for_each(|(k, v)| write_member(out, Some(k), v))?,
+        _ => unreachable!("scalars are written inline"),
+    }
+    out.push('\n');
+    out.push_str(&config.indent.repeat(depth));
+    out.push(close);
+    Ok(())
+}
+
+/// Writes `value` on a single line, with a space after each `,`.
+fn write_inline(out: &mut String, value: &JsonValue, config: &PrettyConfig) -> fmt::Result {
+    match value {
+        JsonValue::Array(arr) => {
+            out.push('[');
+            for (i, v) in arr.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_inline(out, v, config)?;
+            }
+            out.push(']');
+        }
+        JsonValue::Object(map) => {
+            out.push('{');
+            for (i, (k, v)) in map.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_string(out, k)?;
+                out.push_str(if config.space_after_colon { ": " } else { ":" });
+                write_inline(out, v, config)?;
+            }
+            out.push('}');
+        }
+        _ => write_value(out, value, &SerializeOptions::default())?,
+    }
+    Ok(())
+}
+
+fn is_empty_container(value: &JsonValue) -> bool {
+    match value {
+        JsonValue::Array(arr) => arr.is_empty(),
+        JsonValue::Object(map) => map.is_empty(),
+        _ => false,
+    }
+}
+
+/// Non-finite numbers have no JSON representation and are written as `null`.
+pub(crate) fn write_number(f: &mut impl Write, n: f64) -> fmt::Result {
+    if n.is_finite() {
+        write!(f, "{}", n)
+    } else {
+        f.write_str("null")
+    }
+}
+
+pub(crate) fn write_string(f: &mut impl Write, s: &str) -> fmt::Result {
+    write_escaped(f, s, &[])
+}
+
+/// `s` is a `str`, so it can't hold an unpaired surrogate: the parser rejects lone `\uD800`-style
+/// escapes, and every `char` re-encodes as a valid escape or UTF-8. A byte-backed string variant
+/// would need a fallible path that checks for surrogates here instead.
+///
+/// Control characters with a short escape (`\b`, `\f`, `\n`, `\r`, `\t`) use it; the rest are
+/// written as `\u00XX`.
+fn write_escaped(f: &mut impl Write, s: &str, extra_escapes: &[char]) -> fmt::Result {
+    f.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            c if extra_escapes.contains(&c) => {
+                let mut units = [0; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    write!(f, "\\u{:04x}", unit)?;
+                }
+            }
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\u{08}' => f.write_str("\\b")?,
+            '\u{0C}' => f.write_str("\\f")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_json, parse_json_with_options, ParserOptions};
+
+    #[test]
+    fn display_should_work() {
+        let input = r#"[1, 2.5, "a\"b\n", true, null, {"k": []}]"#;
+        let json = parse_json(&mut (&*input)).unwrap();
+        assert_eq!(json.to_string(), r#"[1,2.5,"a\"b\n",true,null,{"k":[]}]"#);
+    }
+
+    #[test]
+    fn display_should_round_trip() {
+        let input = r#"{"name": "John Doe", "marks": [90.0, -80.0, 85.1], "tab": "a\tb", "nested": {"empty": {}}}"#;
+        let json = parse_json(&mut (&*input)).unwrap();
+        let output = json.to_string();
+        assert_eq!(parse_json(&mut output.as_str()).unwrap(), json);
+    }
+
+    #[test]
+    fn control_escapes_should_use_short_forms() {
+        let json = JsonValue::String("a\u{08}b\u{0C}c\n\r\t\u{01}\u{1F}".to_string());
+        assert_eq!(json.to_string(), r#""a\bb\fc\n\r\t\u0001\u001f""#);
+        assert_eq!(parse_json(&mut json.to_string().as_str()).unwrap(), json);
+    }
+
+    #[test]
+    fn serialize_chunks_should_reassemble() {
+        let json = crate::tests::sample();
+        let expected = json.to_string();
+        for size in [1, 7, 64, 10_000] {
+            let chunks: Vec<String> = json.serialize_chunks(size).collect();
+            assert!(chunks.iter().all(|chunk| !chunk.is_empty() && chunk.len() <= size), "{}", size);
+            assert_eq!(chunks.concat(), expected);
+        }
+
+        let json = JsonValue::String("\u{1F600}é".repeat(3));
+        let chunks: Vec<String> = json.serialize_chunks(3).collect();
+        assert_eq!(chunks.concat(), json.to_string());
+        assert!(chunks.iter().all(|chunk| chunk.len() <= 4));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn write_to_should_work() {
+        let json = JsonValue::String("\u{1F600}é".repeat(3));
+        let mut out = Vec::new();
+        json.write_to(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), json.to_string());
+    }
+
+    #[test]
+    fn to_string_truncated_should_stay_within_limit() {
+        let record = parse_json(&mut r#"{"name": "Zoë \u0001\n\"q\"", "tags": ["é", "😀"]}"#).unwrap();
+        let json = JsonValue::Array(vec![record; 1000]);
+        let full = json.to_string();
+        assert_eq!(json.to_string_truncated(full.len()), full);
+
+        for max in 3..200 {
+            let out = json.to_string_truncated(max);
+            assert!(out.len() <= max, "{}", max);
+            let prefix = out.strip_suffix("...").unwrap();
+            assert!(full.starts_with(prefix));
+            assert!(!prefix.ends_with('\\') || prefix.ends_with("\\\\"), "{}", prefix);
+            assert!(prefix.rsplit_once("\\u").is_none_or(|(_, tail)| tail.len() >= 4), "{}", prefix);
+        }
+        assert_eq!(json.to_string_truncated(0), "...");
+        assert!(json.to_string_truncated(1 << 16).len() <= 1 << 16);
+    }
+
+    #[test]
+    fn escape_chars_should_work() {
+        let json = JsonValue::Array(vec![JsonValue::String("a,b \"c\" \u{1F600}".to_string())]);
+        assert_eq!(json.to_string_with(&SerializeOptions::default()), json.to_string());
+
+        let options = SerializeOptions::new().escape_chars([',', '\u{1F600}']);
+        assert_eq!(json.to_string_with(&options), r#"["a\u002cb \"c\" \ud83d\ude00"]"#);
+    }
+
+    #[test]
+    fn skip_empty_should_work() {
+        let json = crate::tests::sample();
+        let options = SerializeOptions::new().skip_empty(true);
+        let output = json.to_string_with(&options);
+        assert!(!output.contains("empty_arr"));
+        assert!(!output.contains("empty_obj"));
+
+        let nested = parse_json(&mut output.as_str()).unwrap().pointer("/nested").unwrap().clone();
+        assert_eq!(nested.as_object().unwrap().len(), 1);
+
+        let input = r#"[[], {}, 1, {"a": {"b": []}}]"#;
+        let json = parse_json(&mut (&*input)).unwrap();
+        assert_eq!(json.to_string_with(&options), r#"[1,{"a":{}}]"#);
+        assert_eq!(JsonValue::Array(Vec::new()).to_string_with(&options), "[]");
+    }
+
+    #[test]
+    fn to_pretty_string_should_indent_with_tabs() {
+        let input = r#"{"a": [1, {"b": null}, [], "x"]}"#;
+        let json = parse_json(&mut (&*input)).unwrap();
+
+        let config = PrettyConfig::new().indent("\t");
+        let expected = "{\n\t\"a\": [\n\t\t1,\n\t\t{\n\t\t\t\"b\": null\n\t\t},\n\t\t[],\n\t\t\"x\"\n\t]\n}";
+        assert_eq!(json.to_pretty_string(&config), expected);
+        assert_eq!(parse_json(&mut expected.to_string().as_str()).unwrap(), json);
+
+        let config = PrettyConfig::new().space_after_colon(false);
+        assert_eq!(JsonValue::Null.to_pretty_string(&config), "null");
+        assert!(json.to_pretty_string(&config).starts_with("{\n  \"a\":[\n    1,"));
+    }
+
+    #[test]
+    fn to_pretty_string_should_inline_scalar_arrays() {
+        let input = r#"{"marks": [[90, 80.5, "x", null], [[1]]]}"#;
+        let json = parse_json(&mut (&*input)).unwrap();
+
+        let config = PrettyConfig::new().inline_scalar_arrays(true);
+        let expected = "{\n  \"marks\": [\n    [90, 80.5, \"x\", null],\n    [\n      [1]\n    ]\n  ]\n}";
+        assert_eq!(json.to_pretty_string(&config), expected);
+
+        let config = PrettyConfig::new().max_width(26);
+        let expected = "{\n  \"marks\": [\n    [90, 80.5, \"x\", null],\n    [[1]]\n  ]\n}";
+        assert_eq!(json.to_pretty_string(&config), expected);
+        assert_eq!(json.to_pretty_string(&PrettyConfig::new().max_width(80)), r#"{"marks": [[90, 80.5, "x", null], [[1]]]}"#);
+    }
+
+    #[test]
+    fn trailing_newline_should_work() {
+        let json = parse_json(&mut r#"{"a": ["x\n"]}"#).unwrap();
+        assert_eq!(json.to_string_with(&SerializeOptions::new()), r#"{"a":["x\n"]}"#);
+
+        let output = json.to_string_with(&SerializeOptions::new().trailing_newline(true));
+        assert_eq!(output, "{\"a\":[\"x\\n\"]}\n");
+        assert_eq!(output.matches('\n').count(), 1);
+    }
+
+    #[test]
+    fn lone_surrogate_should_not_reach_serializer() {
+        for input in [r#""\ud800""#, r#""\udc00""#, r#""\ud800\u0041""#] {
+            assert!(parse_json(&mut (&*input)).is_err(), "{}", input);
+        }
+
+        let input = r#""\ud83d\ude00""#;
+        let json = parse_json(&mut (&*input)).unwrap();
+        let options = SerializeOptions::new().escape_chars(['\u{1F600}']);
+        assert_eq!(json.to_string_with(&options), input);
+    }
+
+    #[test]
+    fn surrogate_neighbours_should_round_trip() {
+        // The code points on either side of the surrogate range, and the astral ones that need a pair.
+        let s = "\u{D7FF}\u{E000}\u{FFFF}\u{10000}\u{1F600}\u{10FFFF}";
+        let json = JsonValue::String(s.to_string());
+        assert_eq!(json.to_string(), format!("\"{}\"", s));
+        assert_eq!(parse_json(&mut json.to_string().as_str()).unwrap(), json);
+
+        let mut escaped = String::new();
+        write_escaped(&mut escaped, s, &s.chars().collect::<Vec<_>>()).unwrap();
+        assert_eq!(escaped, r#""\ud7ff\ue000\uffff\ud800\udc00\ud83d\ude00\udbff\udfff""#);
+        assert_eq!(parse_json(&mut escaped.as_str()).unwrap(), json);
+    }
+
+    #[test]
+    fn preserve_number_text_should_work() {
+        let options = ParserOptions::new().preserve_number_text(true);
+
+        let input = "1.50";
+        let json = parse_json_with_options(&mut (&*input), &options).unwrap();
+        assert_eq!(json, JsonValue::RawNumber("1.50".to_string()));
+        assert_eq!(json.to_string(), "1.50");
+        assert_eq!(json.as_f64(), Some(1.5));
+
+        let json = parse_json(&mut (&*input)).unwrap();
+        assert_eq!(json.to_string(), "1.5");
+    }
+}
@@ -0,0 +1,75 @@
+use alloc::sync::Arc;
+use crate::{JsonValue, Map};
+use crate::prelude::*;
+
+/// A `JsonValue` whose strings and keys live behind `Arc`, so cloning it only copies the
+/// container skeleton while the leaf storage is shared.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SharedJsonValue {
+    String(Arc<str>),
+    Number(f64),
+    Integer(i64),
+    RawNumber(Arc<str>),
+    Boolean(bool),
+    Null,
+    Array(Vec<SharedJsonValue>),
+    Object(Map<Arc<str>, SharedJsonValue>),
+}
+
+impl JsonValue {
+    /// Converts into the `Arc`-backed representation; clone the result to fan it out cheaply.
+    pub fn shared_clone(&self) -> SharedJsonValue {
+        match self {
+            JsonValue::String(s) => SharedJsonValue::String(Arc::from(s.as_str())),
+            JsonValue::Number(n) => SharedJsonValue::Number(*n),
+            JsonValue::Integer(i) => SharedJsonValue::Integer(*i),
+            JsonValue::RawNumber(s) => SharedJsonValue::RawNumber(Arc::from(s.as_str())),
+            JsonValue::Boolean(b) => SharedJsonValue::Boolean(*b),
+            JsonValue::Null => SharedJsonValue::Null,
+            JsonValue::Array(arr) => SharedJsonValue::Array(arr.iter().map(JsonValue::shared_clone).collect()),
+            JsonValue::Object(map) => SharedJsonValue::Object(
+                map.iter().map(|(k, v)| (Arc::from(k.as_str()), v.shared_clone())).collect(),
+            ),
+        }
+    }
+}
+
+impl From<&SharedJsonValue> for JsonValue {
+    fn from(value: &SharedJsonValue) -> Self {
+        match value {
+            SharedJsonValue::String(s) => JsonValue::String(s.to_string()),
+            SharedJsonValue::Number(n) => JsonValue::Number(*n),
+            SharedJsonValue::Integer(i) => JsonValue::Integer(*i),
+            SharedJsonValue::RawNumber(s) => JsonValue::RawNumber(s.to_string()),
+            SharedJsonValue::Boolean(b) => JsonValue::Boolean(*b),
+            SharedJsonValue::Null => JsonValue::Null,
+            SharedJsonValue::Array(arr) => JsonValue::Array(arr.iter().map(JsonValue::from).collect()),
+            SharedJsonValue::Object(map) => JsonValue::Object(
+                map.iter().map(|(k, v)| (k.to_string(), JsonValue::from(v))).collect(),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::sample;
+
+    #[test]
+    fn shared_clone_should_share_leaves() {
+        let json = sample();
+        let shared = json.shared_clone();
+        let mut copy = shared.clone();
+
+        let (SharedJsonValue::Object(a), SharedJsonValue::Object(b)) = (&shared, &copy) else { panic!("expected objects") };
+        let (SharedJsonValue::String(x), SharedJsonValue::String(y)) = (&a["name"], &b["name"]) else { panic!("expected strings") };
+        assert!(Arc::ptr_eq(x, y));
+
+        if let SharedJsonValue::Object(map) = &mut copy {
+            map.remove("name");
+        }
+        assert_eq!(JsonValue::from(&shared), json);
+        assert_ne!(JsonValue::from(&copy), json);
+    }
+}
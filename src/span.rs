@@ -0,0 +1,162 @@
+use core::ops::Range;
+use anyhow::Result;
+use winnow::Parser;
+use winnow::ascii::multispace0;
+use winnow::combinator::preceded;
+use crate::options::ParseState;
+use crate::{expect_value, syntax_error, Input, JsonValue, ParserOptions};
+use crate::prelude::*;
+
+/// Where a value sits in the source, with the spans of its elements or members in source order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    /// Byte range of the value, from its first to its last character.
+    pub range: Range<usize>,
+    /// The member's key, for values directly inside an object.
+    pub key: Option<String>,
+    pub children: Vec<Span>,
+}
+
+/// Builds the `Span` tree alongside `parse_value`'s frame stack.
+///
+/// Positions are recorded as the length of the input remaining, since that's all the parser
+/// sees; `finish` turns them into offsets into the source.
+#[derive(Debug, Default)]
+pub(crate) struct SpanRecorder {
+    open: Vec<Span>,
+    root: Option<Span>,
+}
+
+impl SpanRecorder {
+    /// Starts a container, whose children are recorded until the matching `close`.
+    pub(crate) fn open(&mut self, remaining: usize, key: Option<String>) {
+        self.open.push(Span { range: remaining..0, key, children: Vec::new() });
+    }
+
+    pub(crate) fn close(&mut self, remaining: usize) {
+        if let Some(mut span) = self.open.pop() {
+            span.range.end = remaining;
+            self.attach(span);
+        }
+    }
+
+    pub(crate) fn leaf(&mut self, start: usize, end: usize, key: Option<String>) {
+        self.attach(Span { range: start..end, key, children: Vec::new() });
+    }
+
+    fn attach(&mut self, span: Span) {
+        match self.open.last_mut() {
+            Some(parent) => parent.children.push(span),
+            None => self.root = Some(span),
+        }
+    }
+
+    pub(crate) fn finish(self, source: &str) -> Option<Span> {
+        let mut root = self.root?;
+        to_offsets(&mut root, source);
+        Some(root)
+    }
+}
+
+/// The parser may have consumed whitespace after a value before its end was recorded, so ends
+/// are trimmed back to the value's last character.
+fn to_offsets(span: &mut Span, source: &str) {
+    let start = source.len() - span.range.start;
+    let end = source[..source.len() - span.range.end].trim_end().len();
+    span.range = start..end;
+    span.children.iter_mut().for_each(|child| to_offsets(child, source));
+}
+
+/// Parses one value off the front of `input` like `parse_value_partial`, returning it with its
+/// span tree and the number of bytes consumed, including whitespace around the value.
+pub fn parse_prefix_spanned(input: &str) -> Result<(JsonValue, Span, usize)> {
+    let options = ParserOptions::default();
+    let mut state = ParseState::new(&options);
+    state.spans = Some(Default::default());
+    let mut stateful = Input { input, state: &state };
+
+    let value = preceded(multispace0, expect_value)
+        .parse_next(&mut stateful)
+        .map_err(|e| syntax_error(input, &stateful, e))?;
+    let consumed = input.len() - stateful.input.len();
+
+    let span = state.spans.take()
+        .and_then(|spans| spans.into_inner().finish(input))
+        .expect("a parsed value has a span");
+    Ok((value, span, consumed))
+}
+
+/// Parses whitespace-separated top-level values, like `parse_many`, returning each with its byte
+/// range in `input`, e.g. to split a file of concatenated JSON back into separate documents.
+pub fn parse_documents(input: &str) -> Result<Vec<(JsonValue, Range<usize>)>> {
+    let options = ParserOptions::default();
+    let state = ParseState::new(&options);
+    let mut stateful = Input { input, state: &state };
+    let offset = |rest: &str| input.len() - rest.len();
+
+    let mut documents = Vec::new();
+    loop {
+        multispace0.parse_next(&mut stateful).map_err(|e| syntax_error(input, &stateful, e))?;
+        if stateful.input.is_empty() {
+            return Ok(documents);
+        }
+        let start = offset(stateful.input);
+        let value = expect_value
+            .parse_next(&mut stateful)
+            .map_err(|e| syntax_error(input, &stateful, e))?;
+        // Containers swallow the whitespace after them, so trim the range back to the value.
+        let end = input[..offset(stateful.input)].trim_end().len();
+        documents.push((value, start..end));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(range: Range<usize>, key: Option<&str>, children: Vec<Span>) -> Span {
+        Span { range, key: key.map(str::to_string), children }
+    }
+
+    #[test]
+    fn parse_prefix_spanned_should_work() {
+        let input = "[1,2] trailing";
+        let (value, tree, consumed) = parse_prefix_spanned(input).unwrap();
+        assert_eq!(value, JsonValue::Array(vec![JsonValue::Number(1.0), JsonValue::Number(2.0)]));
+        assert_eq!(tree, span(0..5, None, vec![span(1..2, None, vec![]), span(3..4, None, vec![])]));
+        assert_eq!(consumed, 6);
+        assert_eq!(&input[consumed..], "trailing");
+    }
+
+    #[test]
+    fn parse_prefix_spanned_should_track_keys() {
+        let input = r#" {"a": [ {} ], "bc": "x" } , 1"#;
+        let (_, tree, consumed) = parse_prefix_spanned(input).unwrap();
+        assert_eq!(tree.range, 1..26);
+        assert_eq!(&input[consumed..], ", 1");
+
+        assert_eq!(tree.children, vec![
+            span(7..13, Some("a"), vec![span(9..11, None, vec![])]),
+            span(21..24, Some("bc"), vec![]),
+        ]);
+        assert_eq!(&input[tree.children[1].range.clone()], r#""x""#);
+
+        let (_, tree, _) = parse_prefix_spanned("  42 ").unwrap();
+        assert_eq!(tree, span(2..4, None, vec![]));
+    }
+
+    #[test]
+    fn parse_documents_should_work() {
+        let input = "{\"a\": 1}\n  {\"b\": [true]} 7\n";
+        let documents = parse_documents(input).unwrap();
+        assert_eq!(documents.len(), 3);
+        assert_eq!(documents[0].1, 0..8);
+        assert_eq!(documents[1].1, 11..24);
+        assert_eq!(&input[documents[1].1.clone()], "{\"b\": [true]}");
+        assert_eq!(documents[1].0, crate::parse_json(&mut r#"{"b": [true]}"#).unwrap());
+        assert_eq!(documents[2], (JsonValue::Number(7.0), 25..26));
+
+        assert_eq!(parse_documents("  ").unwrap(), vec![]);
+        assert!(parse_documents("{} {").is_err());
+    }
+}
@@ -0,0 +1,170 @@
+use anyhow::Result;
+use winnow::{Parser, PResult};
+use winnow::ascii::multispace0;
+use winnow::combinator::{cut_err, eof, opt, preceded};
+use winnow::error::{StrContext, StrContextValue};
+use winnow::token::one_of;
+use crate::options::ParseState;
+use crate::{expect_value, expected, syntax_error, Input, JsonValue, ParserOptions};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Position {
+    /// Nothing parsed yet, not even the `[`.
+    Start,
+    /// Just after the `[` or an element, with a `,` or `]` due unless no element was read yet.
+    Elements { first: bool },
+    /// The `]` was reached or an error was returned.
+    Done,
+}
+
+/// Parses a top-level JSON array one element at a time, for arrays of records too large to hold
+/// in memory at once.
+///
+/// Each call to `next` parses only as far as the next element, so a syntax error further on is
+/// reported once the elements before it have been yielded. After an error the iterator is done.
+#[derive(Debug, Clone)]
+pub struct ArrayStreamParser<'a> {
+    source: &'a str,
+    rest: &'a str,
+    position: Position,
+    /// Whether only whitespace may follow the `]`.
+    to_end: bool,
+}
+
+impl<'a> ArrayStreamParser<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self { source: input, rest: input, position: Position::Start, to_end: true }
+    }
+
+    /// The next element, or `None` once the closing `]` and anything after it was parsed.
+    fn step(&mut self, input: &mut Input) -> PResult<Option<JsonValue>> {
+        let first = match self.position {
+            Position::Start => {
+                cut_err(preceded(multispace0, '[').context(expected('['))).parse_next(input)?;
+                multispace0.parse_next(input)?;
+                true
+            }
+            Position::Elements { first } => first,
+            Position::Done => return Ok(None),
+        };
+
+        let close = if first {
+            opt(']').parse_next(input)?.is_some()
+        } else {
+            let sep = cut_err(one_of([',', ']']).context(expected(']')).context(expected(',')))
+                .parse_next(input)?;
+            multispace0.parse_next(input)?;
+            sep == ']'
+        };
+        if close {
+            self.position = Position::Done;
+            if self.to_end {
+                let end = eof.context(StrContext::Expected(StrContextValue::Description("end of input")));
+                cut_err(preceded(multispace0, end)).parse_next(input)?;
+            }
+            return Ok(None);
+        }
+
+        let value = expect_value.parse_next(input)?;
+        multispace0.parse_next(input)?;
+        self.position = Position::Elements { first: false };
+        Ok(Some(value))
+    }
+}
+
+impl Iterator for ArrayStreamParser<'_> {
+    type Item = Result<JsonValue>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let options = ParserOptions::default();
+        let state = ParseState::new(&options);
+        let mut input = Input { input: self.rest, state: &state };
+
+        let ret = self.step(&mut input);
+        self.rest = input.input;
+        match ret {
+            Ok(value) => value.map(Ok),
+            Err(e) => {
+                self.position = Position::Done;
+                Some(Err(syntax_error(self.source, &input, e).into()))
+            }
+        }
+    }
+}
+
+/// Parses the array at the front of `input` element by element, advancing `input` as it goes,
+/// so a consumer that only folds over the elements never has them all in memory at once.
+///
+/// Once the iterator is exhausted `input` is past the `]` and any whitespace after it, with the
+/// rest left for the caller; after an error it's where the error occurred.
+pub fn parse_array_lazy<'c, 'a>(input: &'c mut &'a str) -> LazyArray<'c, 'a> {
+    let inner = ArrayStreamParser { to_end: false, ..ArrayStreamParser::new(input) };
+    LazyArray { cursor: input, inner }
+}
+
+/// The iterator returned by `parse_array_lazy`.
+#[derive(Debug)]
+pub struct LazyArray<'c, 'a> {
+    cursor: &'c mut &'a str,
+    inner: ArrayStreamParser<'a>,
+}
+
+impl Iterator for LazyArray<'_, '_> {
+    type Item = Result<JsonValue>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let ret = self.inner.next();
+        *self.cursor = self.inner.rest;
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{JsonError, Map};
+    use crate::prelude::*;
+
+    #[test]
+    fn array_stream_parser_should_work() {
+        let mut stream = ArrayStreamParser::new(r#" [ {"id": 1}, "two" ,[3] ] "#);
+        let mut record = Map::new();
+        record.insert("id".to_string(), JsonValue::Number(1.0));
+        assert_eq!(stream.next().unwrap().unwrap(), JsonValue::Object(record));
+        assert_eq!(stream.next().unwrap().unwrap(), JsonValue::String("two".to_string()));
+        assert_eq!(stream.next().unwrap().unwrap(), JsonValue::Array(vec![JsonValue::Number(3.0)]));
+        assert!(stream.next().is_none());
+        assert!(stream.next().is_none());
+
+        assert_eq!(ArrayStreamParser::new("[]").count(), 0);
+    }
+
+    #[test]
+    fn array_stream_parser_should_stop_at_error() {
+        let mut stream = ArrayStreamParser::new("[1, 2 3]");
+        assert_eq!(stream.next().unwrap().unwrap(), JsonValue::Number(1.0));
+        assert_eq!(stream.next().unwrap().unwrap(), JsonValue::Number(2.0));
+        let err = stream.next().unwrap().unwrap_err().downcast::<JsonError>().unwrap();
+        assert!(matches!(err, JsonError::Syntax { offset: 6, .. }));
+        assert!(stream.next().is_none());
+
+        assert!(ArrayStreamParser::new(r#"{"a": 1}"#).next().unwrap().is_err());
+        let results: Vec<_> = ArrayStreamParser::new("[1] x").collect();
+        assert!(results[0].is_ok() && results[1].is_err());
+    }
+
+    #[test]
+    fn parse_array_lazy_should_work() {
+        let mut input = " [1, 2.5, -3, 10] , rest";
+        let sum = parse_array_lazy(&mut input).try_fold(0.0, |sum, v| v.map(|v| sum + v.as_f64().unwrap()));
+        assert_eq!(sum.unwrap(), 10.5);
+        assert_eq!(input, ", rest");
+
+        let mut input = "[1, x]";
+        let mut elements = parse_array_lazy(&mut input);
+        assert!(elements.next().unwrap().is_ok());
+        assert!(elements.next().unwrap().is_err());
+        assert!(elements.next().is_none());
+        assert_eq!(input, "x]");
+    }
+}
@@ -0,0 +1,262 @@
+use alloc::borrow::Cow;
+use anyhow::Result;
+use winnow::{Parser, PResult};
+use winnow::ascii::multispace0;
+use winnow::combinator::{alt, cut_err, delimited, eof, fail, repeat};
+use winnow::error::{StrContext, StrContextValue};
+use winnow::stream::Stream;
+use winnow::token::{any, one_of, take_till};
+use crate::options::ParseState;
+use crate::{expected, parse_boolean, parse_json_number, parse_null, parse_string, syntax_error, Input, ParserOptions};
+use crate::prelude::*;
+
+/// One lexical event of a JSON document, as yielded by `Tokenizer`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token<'a> {
+    BeginArray,
+    EndArray,
+    BeginObject,
+    EndObject,
+    /// An object key, read together with its `:`; the member's value follows.
+    Key(Cow<'a, str>),
+    String(Cow<'a, str>),
+    /// The number's source literal.
+    Number(&'a str),
+    Boolean(bool),
+    Null,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Container {
+    Array,
+    Object,
+}
+
+impl Container {
+    fn end_token(self) -> Token<'static> {
+        match self {
+            Container::Array => Token::EndArray,
+            Container::Object => Token::EndObject,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Expect {
+    Value,
+    /// Just after `[`: a value or `]`.
+    FirstElement,
+    /// Just after `{`: a key or `}`.
+    FirstKey,
+    Key,
+    /// After a value inside a container: `,` or the closing bracket.
+    Separator,
+    /// After the top-level value: only whitespace may follow.
+    End,
+    /// The input was fully read or an error was returned.
+    Done,
+}
+
+/// Reads a JSON document as a flat sequence of `Token`s without building a `JsonValue`.
+///
+/// Strings and keys are borrowed from the input where possible. With `decode_strings(false)`
+/// they're always borrowed raw, exactly as written between the quotes with escapes left
+/// undecoded, so callers that only skip over strings don't pay for decoding; escape sequences
+/// aren't checked in that mode. After an error the iterator is done.
+#[derive(Debug, Clone)]
+pub struct Tokenizer<'a> {
+    source: &'a str,
+    rest: &'a str,
+    decode: bool,
+    stack: Vec<Container>,
+    expect: Expect,
+}
+
+impl<'a> Tokenizer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self { source: input, rest: input, decode: true, stack: Vec::new(), expect: Expect::Value }
+    }
+
+    /// Whether escapes in strings and keys are decoded; on by default.
+    pub fn decode_strings(mut self, decode: bool) -> Self {
+        self.decode = decode;
+        self
+    }
+
+    /// Where to go once a value, scalar or container, is complete.
+    fn after_value(&self) -> Expect {
+        if self.stack.is_empty() { Expect::End } else { Expect::Separator }
+    }
+
+    fn step(&mut self, input: &mut Input<'a, '_>) -> PResult<Option<Token<'a>>> {
+        loop {
+            multispace0.parse_next(input)?;
+            match self.expect {
+                Expect::Value => return self.value(input).map(Some),
+                Expect::FirstElement if input.input.starts_with(']') => {
+                    return self.close(input, Container::Array).map(Some);
+                }
+                Expect::FirstElement => return self.value(input).map(Some),
+                Expect::FirstKey if input.input.starts_with('}') => {
+                    return self.close(input, Container::Object).map(Some);
+                }
+                Expect::FirstKey | Expect::Key => {
+                    let key = cut_err(|input: &mut Input<'a, '_>| self.string(input))
+                        .context(StrContext::Expected(StrContextValue::Description("string")))
+                        .parse_next(input)?;
+                    cut_err(delimited(multispace0, ':', multispace0).context(expected(':'))).parse_next(input)?;
+                    self.expect = Expect::Value;
+                    return Ok(Some(Token::Key(key)));
+                }
+                Expect::Separator => {
+                    let container = *self.stack.last().expect("a separator is only expected in a container");
+                    let close = if container == Container::Array { ']' } else { '}' };
+                    let sep = cut_err(one_of([',', close]).context(expected(close)).context(expected(',')))
+                        .parse_next(input)?;
+                    match sep {
+                        ',' if container == Container::Array => self.expect = Expect::Value,
+                        ',' => self.expect = Expect::Key,
+                        _ => {
+                            self.pop(container);
+                            return Ok(Some(container.end_token()));
+                        }
+                    }
+                }
+                Expect::End => {
+                    self.expect = Expect::Done;
+                    cut_err(eof.context(StrContext::Expected(StrContextValue::Description("end of input"))))
+                        .parse_next(input)?;
+                    return Ok(None);
+                }
+                Expect::Done => return Ok(None),
+            }
+        }
+    }
+
+    fn close(&mut self, input: &mut Input<'a, '_>, container: Container) -> PResult<Token<'a>> {
+        any.parse_next(input)?;
+        self.pop(container);
+        Ok(container.end_token())
+    }
+
+    fn pop(&mut self, container: Container) {
+        debug_assert_eq!(self.stack.last(), Some(&container));
+        self.stack.pop();
+        self.expect = self.after_value();
+    }
+
+    fn value(&mut self, input: &mut Input<'a, '_>) -> PResult<Token<'a>> {
+        let token = match input.input.chars().next() {
+            Some('[') => {
+                any.parse_next(input)?;
+                self.stack.push(Container::Array);
+                self.expect = Expect::FirstElement;
+                return Ok(Token::BeginArray);
+            }
+            Some('{') => {
+                any.parse_next(input)?;
+                self.stack.push(Container::Object);
+                self.expect = Expect::FirstKey;
+                return Ok(Token::BeginObject);
+            }
+            Some('"') => Token::String(self.string(input)?),
+            _ => cut_err(alt((
+                parse_null.value(Token::Null),
+                parse_json_number.take().map(Token::Number),
+                parse_boolean.map(Token::Boolean),
+                fail.context(StrContext::Expected(StrContextValue::Description("value"))),
+            ))).parse_next(input)?,
+        };
+        self.expect = self.after_value();
+        Ok(token)
+    }
+
+    /// A string's contents: borrowed raw unless there are escapes to decode.
+    fn string(&self, input: &mut Input<'a, '_>) -> PResult<Cow<'a, str>> {
+        let start = input.checkpoint();
+        let raw = delimited(
+            '"',
+            repeat(0.., alt((take_till(1.., ['"', '\\']).void(), ('\\', any).void())))
+                .map(|()| ())
+                .take(),
+            cut_err('"'.context(expected('"'))),
+        ).parse_next(input)?;
+        if !self.decode || !raw.contains('\\') {
+            return Ok(Cow::Borrowed(raw));
+        }
+        input.reset(&start);
+        cut_err(parse_string).map(Cow::Owned).parse_next(input)
+    }
+}
+
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = Result<Token<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let options = ParserOptions::default();
+        let state = ParseState::new(&options);
+        let mut input = Input { input: self.rest, state: &state };
+
+        let ret = self.step(&mut input);
+        self.rest = input.input;
+        match ret {
+            Ok(token) => token.map(Ok),
+            Err(e) => {
+                self.expect = Expect::Done;
+                Some(Err(syntax_error(self.source, &input, e).into()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(tokenizer: Tokenizer) -> Vec<Token> {
+        tokenizer.collect::<Result<_>>().unwrap()
+    }
+
+    #[test]
+    fn tokenizer_should_work() {
+        let input = r#" {"a\tb": [1.5, "x", true, null, {}], "c": []} "#;
+        assert_eq!(tokens(Tokenizer::new(input)), vec![
+            Token::BeginObject,
+            Token::Key(Cow::Owned("a\tb".to_string())),
+            Token::BeginArray,
+            Token::Number("1.5"),
+            Token::String(Cow::Borrowed("x")),
+            Token::Boolean(true),
+            Token::Null,
+            Token::BeginObject,
+            Token::EndObject,
+            Token::EndArray,
+            Token::Key(Cow::Borrowed("c")),
+            Token::BeginArray,
+            Token::EndArray,
+            Token::EndObject,
+        ]);
+        assert_eq!(tokens(Tokenizer::new("-2e3")), vec![Token::Number("-2e3")]);
+    }
+
+    #[test]
+    fn tokenizer_should_pass_raw_strings() {
+        let input = r#"["a\"b\u00e9\n", "\x"]"#;
+        let raw = tokens(Tokenizer::new(input).decode_strings(false));
+        assert_eq!(raw[1], Token::String(Cow::Borrowed(r#"a\"b\u00e9\n"#)));
+        assert_eq!(raw[2], Token::String(Cow::Borrowed(r#"\x"#)));
+
+        let mut decoded = Tokenizer::new(input);
+        decoded.next();
+        assert_eq!(decoded.next().unwrap().unwrap(), Token::String(Cow::Owned("a\"bé\n".to_string())));
+        assert!(decoded.next().unwrap().is_err());
+        assert!(decoded.next().is_none());
+    }
+
+    #[test]
+    fn tokenizer_should_reject_malformed_input() {
+        for input in ["[1,]", "[1 2]", r#"{"a" 1}"#, "{1: 2}", "[1}", "1 2", "[", r#""abc"#] {
+            assert!(Tokenizer::new(input).any(|token| token.is_err()), "{}", input);
+        }
+    }
+}
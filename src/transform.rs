@@ -0,0 +1,264 @@
+use crate::{parse_number_str, JsonValue};
+use crate::prelude::*;
+
+impl JsonValue {
+    /// Recursively turns string values holding a valid JSON number, like `"42"`, into numbers.
+    ///
+    /// Object keys and non-numeric strings are left untouched.
+    pub fn coerce_numeric_strings(&mut self) {
+        match self {
+            JsonValue::String(s) => {
+                if let Some(n) = parse_number_str(s) {
+                    *self = JsonValue::Number(n);
+                }
+            }
+            JsonValue::Array(arr) => arr.iter_mut().for_each(JsonValue::coerce_numeric_strings),
+            JsonValue::Object(map) => map.values_mut().for_each(JsonValue::coerce_numeric_strings),
+            _ => {}
+        }
+    }
+
+    /// Recursively turns string values equal to `"null"` into `null`, as produced by naive
+    /// CSV-to-JSON conversions.
+    pub fn coerce_null_strings(&mut self) {
+        self.coerce_null_strings_in(&["null"]);
+    }
+
+    /// Like `coerce_null_strings`, but turns strings equal to any of `markers` into `null`,
+    /// e.g. `&["null", ""]` to also treat empty cells as missing.
+    pub fn coerce_null_strings_in(&mut self, markers: &[&str]) {
+        match self {
+            JsonValue::String(s) if markers.contains(&s.as_str()) => *self = JsonValue::Null,
+            JsonValue::Array(arr) => arr.iter_mut().for_each(|v| v.coerce_null_strings_in(markers)),
+            JsonValue::Object(map) => map.values_mut().for_each(|v| v.coerce_null_strings_in(markers)),
+            _ => {}
+        }
+    }
+
+    /// Recursively turns numbers into strings holding their JSON text, the inverse of
+    /// `coerce_numeric_strings`. `RawNumber` literals keep their exact source text.
+    ///
+    /// Non-finite numbers have no JSON text and are left as they are.
+    pub fn stringify_numbers(&mut self) {
+        match self {
+            JsonValue::Number(n) if n.is_finite() => *self = JsonValue::String(n.to_string()),
+            JsonValue::Integer(i) => *self = JsonValue::String(i.to_string()),
+            JsonValue::RawNumber(s) => *self = JsonValue::String(core::mem::take(s)),
+            JsonValue::Array(arr) => arr.iter_mut().for_each(JsonValue::stringify_numbers),
+            JsonValue::Object(map) => map.values_mut().for_each(JsonValue::stringify_numbers),
+            _ => {}
+        }
+    }
+
+    /// Recursively replaces every array holding exactly one element with that element, so
+    /// `{"tags": ["x"]}` becomes `{"tags": "x"}` and `[[1]]` becomes `1`.
+    pub fn unwrap_single_arrays(&mut self) {
+        self.unwrap_singletons(None);
+    }
+
+    /// Like `unwrap_single_arrays`, but only unwraps arrays held directly under one of `keys`.
+    pub fn unwrap_single_arrays_in(&mut self, keys: &[&str]) {
+        self.unwrap_singletons(Some(keys));
+    }
+
+    fn unwrap_singletons(&mut self, keys: Option<&[&str]>) {
+        match self {
+            JsonValue::Array(arr) => arr.iter_mut().for_each(|v| v.unwrap_singletons(keys)),
+            JsonValue::Object(map) => {
+                for (k, v) in map.iter_mut() {
+                    v.unwrap_singletons(keys);
+                    if keys.is_some_and(|keys| keys.contains(&k.as_str())) {
+                        v.unwrap_singleton();
+                    }
+                }
+            }
+            _ => {}
+        }
+        if keys.is_none() {
+            self.unwrap_singleton();
+        }
+    }
+
+    fn unwrap_singleton(&mut self) {
+        while let JsonValue::Array(arr) = self {
+            if arr.len() != 1 {
+                break;
+            }
+            *self = arr.remove(0);
+        }
+    }
+
+    /// Recursively rounds every `Number` to `decimals` decimal places.
+    ///
+    /// Whole numbers and `RawNumber` literals are left as they are.
+    pub fn round_floats(&mut self, decimals: u32) {
+        match self {
+            JsonValue::Number(n) if n.fract() != 0.0 => {
+                let factor = 10_f64.powi(decimals as i32);
+                *n = (*n * factor).round() / factor;
+            }
+            JsonValue::Array(arr) => arr.iter_mut().for_each(|v| v.round_floats(decimals)),
+            JsonValue::Object(map) => map.values_mut().for_each(|v| v.round_floats(decimals)),
+            _ => {}
+        }
+    }
+
+    /// Recursively replaces every value equal to `from` with a clone of `to`, e.g. to redact a
+    /// known secret everywhere it appears. Replacements aren't searched again, so `to` may
+    /// contain `from`.
+    pub fn replace_all(&mut self, from: &JsonValue, to: JsonValue) {
+        self.replace_matching(from, &to);
+    }
+
+    fn replace_matching(&mut self, from: &JsonValue, to: &JsonValue) {
+        if self == from {
+            *self = to.clone();
+            return;
+        }
+        match self {
+            JsonValue::Array(arr) => arr.iter_mut().for_each(|v| v.replace_matching(from, to)),
+            JsonValue::Object(map) => map.values_mut().for_each(|v| v.replace_matching(from, to)),
+            _ => {}
+        }
+    }
+
+    /// Returns a copy of the tree with every scalar passed through `f`, which returns the
+    /// replacement or `None` to keep the scalar as it is. Containers are rebuilt around their
+    /// mapped contents and `self` is left untouched.
+    pub fn map_values(&self, f: impl Fn(&JsonValue) -> Option<JsonValue>) -> JsonValue {
+        self.map_scalars(&f)
+    }
+
+    fn map_scalars(&self, f: &impl Fn(&JsonValue) -> Option<JsonValue>) -> JsonValue {
+        match self {
+            JsonValue::Array(arr) => JsonValue::Array(arr.iter().map(|v| v.map_scalars(f)).collect()),
+            JsonValue::Object(map) => {
+                JsonValue::Object(map.iter().map(|(k, v)| (k.clone(), v.map_scalars(f))).collect())
+            }
+            scalar => f(scalar).unwrap_or_else(|| scalar.clone()),
+        }
+    }
+
+    /// Recursively rounds every `Number` to the nearest `f32`, stored back as an `f64`.
+    ///
+    /// This is lossy: only about 7 significant digits survive, integers above 2^24 may change,
+    /// and numbers beyond `f32::MAX` become infinite. `RawNumber` literals are left as they are.
+    pub fn to_f32_lossy(&mut self) {
+        match self {
+            JsonValue::Number(n) => *n = *n as f32 as f64,
+            JsonValue::Array(arr) => arr.iter_mut().for_each(JsonValue::to_f32_lossy),
+            JsonValue::Object(map) => map.values_mut().for_each(JsonValue::to_f32_lossy),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_json;
+
+    fn json(input: &str) -> JsonValue {
+        parse_json(&mut (&*input)).unwrap()
+    }
+
+    #[test]
+    fn coerce_numeric_strings_should_work() {
+        let mut value = json(r#"{"a": "42", "b": "x", "c": ["-1.5e2", " 7", "1_0"]}"#);
+        value.coerce_numeric_strings();
+        assert_eq!(value, json(r#"{"a": 42, "b": "x", "c": [-150, " 7", "1_0"]}"#));
+    }
+
+    #[test]
+    fn coerce_null_strings_should_work() {
+        let mut value = json(r#"{"a": "null", "b": "x"}"#);
+        value.coerce_null_strings();
+        assert_eq!(value, json(r#"{"a": null, "b": "x"}"#));
+
+        let mut value = json(r#"[["null", "", "NULL"], {"null": " "}]"#);
+        value.coerce_null_strings_in(&["null", ""]);
+        assert_eq!(value, json(r#"[[null, null, "NULL"], {"null": " "}]"#));
+    }
+
+    #[test]
+    fn map_values_should_work() {
+        let original = json(r#"{"a": 1, "b": ["x", 2.5, null], "c": {"d": -3}}"#);
+        let doubled = original.map_values(|v| v.as_f64().map(|n| JsonValue::Number(n * 2.0)));
+        assert_eq!(doubled, json(r#"{"a": 2, "b": ["x", 5, null], "c": {"d": -6}}"#));
+        assert_eq!(original, json(r#"{"a": 1, "b": ["x", 2.5, null], "c": {"d": -3}}"#));
+    }
+
+    #[test]
+    fn stringify_numbers_should_work() {
+        let mut value = json(r#"{"a": 42, "b": "x", "c": [-1.5e2, true, null, 0.5]}"#);
+        value.stringify_numbers();
+        assert_eq!(value, json(r#"{"a": "42", "b": "x", "c": ["-150", true, null, "0.5"]}"#));
+
+        let options = crate::ParserOptions::new().preserve_number_text(true);
+        let mut value = crate::parse_json_with_options(&mut "[12345678901234567890, 1.50]", &options).unwrap();
+        value.stringify_numbers();
+        assert_eq!(value.to_string(), r#"["12345678901234567890","1.50"]"#);
+
+        value.coerce_numeric_strings();
+        assert_eq!(value.pointer("/1"), Some(&JsonValue::Number(1.5)));
+    }
+
+    #[test]
+    fn unwrap_single_arrays_should_work() {
+        let mut value = json(r#"{"tags": ["x"]}"#);
+        value.unwrap_single_arrays();
+        assert_eq!(value, json(r#"{"tags": "x"}"#));
+
+        let mut value = json(r#"[{"a": [[1]], "b": [1, 2], "c": []}]"#);
+        value.unwrap_single_arrays();
+        assert_eq!(value, json(r#"{"a": 1, "b": [1, 2], "c": []}"#));
+    }
+
+    #[test]
+    fn unwrap_single_arrays_in_should_work() {
+        let mut value = json(r#"{"tags": ["x"], "ids": [1], "nested": [{"tags": [["y"]]}]}"#);
+        value.unwrap_single_arrays_in(&["tags"]);
+        assert_eq!(value, json(r#"{"tags": "x", "ids": [1], "nested": [{"tags": "y"}]}"#));
+    }
+
+    #[test]
+    fn round_floats_should_work() {
+        let mut value = json(r#"{"x": 1.23456, "y": [2.71828, -0.005, 10], "s": "1.2345"}"#);
+        value.round_floats(2);
+        assert_eq!(value.pointer("/x"), Some(&JsonValue::Number(1.23)));
+        assert_eq!(
+            value.pointer("/y"),
+            Some(&JsonValue::Array(vec![JsonValue::Number(2.72), JsonValue::Number(-0.01), JsonValue::Number(10.0)]))
+        );
+        assert_eq!(value.get_str("/s"), Some("1.2345"));
+    }
+
+    #[test]
+    fn to_f32_lossy_should_work() {
+        let mut value = JsonValue::Array(vec![
+            JsonValue::Number(0.1),
+            JsonValue::Number(16777217.0),
+            JsonValue::String("0.1".to_string()),
+        ]);
+        value.to_f32_lossy();
+        assert_eq!(
+            value,
+            JsonValue::Array(vec![
+                JsonValue::Number(0.10000000149011612),
+                JsonValue::Number(16777216.0),
+                JsonValue::String("0.1".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn replace_all_should_work() {
+        let mut value = json(r#"{"user": "s3cret", "log": ["s3cret", {"token": "s3cret", "other": "x"}], "s3cret": 1}"#);
+        value.replace_all(&JsonValue::String("s3cret".to_string()), JsonValue::String("***".to_string()));
+        assert_eq!(value, json(r#"{"user": "***", "log": ["***", {"token": "***", "other": "x"}], "s3cret": 1}"#));
+
+        let mut value = json("[1, [1]]");
+        value.replace_all(&JsonValue::Number(1.0), json("[1]"));
+        assert_eq!(value, json("[[1], [[1]]]"));
+    }
+}
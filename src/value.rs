@@ -0,0 +1,516 @@
+use core::ops::RangeInclusive;
+use crate::{JsonError, JsonKind, JsonValue, Map};
+use crate::prelude::*;
+
+impl JsonValue {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            JsonValue::Number(n) => Some(*n),
+            JsonValue::Integer(i) => Some(*i as f64),
+            JsonValue::RawNumber(s) => s.parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// The value of an `Integer`, or of a whole `Number` or `RawNumber` that fits in `i64`.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            JsonValue::Integer(i) => Some(*i),
+            JsonValue::RawNumber(s) if s.parse::<i64>().is_ok() => s.parse().ok(),
+            JsonValue::Number(_) | JsonValue::RawNumber(_) => {
+                let n = self.as_f64()?;
+                (n.fract() == 0.0 && n >= i64::MIN as f64 && n < i64::MAX as f64).then_some(n as i64)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            JsonValue::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Like `as_bool`, but also reads flags from inconsistently typed input: the strings
+    /// `"true"`, `"yes"` and `"1"` and the number `1` are `true`; `"false"`, `"no"`, `"0"` and
+    /// `0` are `false`. Strings must match exactly, in lowercase and without whitespace; anything
+    /// else is `None`.
+    pub fn as_bool_loose(&self) -> Option<bool> {
+        match self {
+            JsonValue::Boolean(b) => Some(*b),
+            JsonValue::String(s) => match s.as_str() {
+                "true" | "yes" | "1" => Some(true),
+                "false" | "no" | "0" => Some(false),
+                _ => None,
+            },
+            JsonValue::Number(_) | JsonValue::Integer(_) | JsonValue::RawNumber(_) => match self.as_f64() {
+                Some(1.0) => Some(true),
+                Some(0.0) => Some(false),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<JsonValue>> {
+        match self {
+            JsonValue::Array(arr) => Some(arr),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&Map<String, JsonValue>> {
+        match self {
+            JsonValue::Object(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    /// The object's entries sorted by key, without reordering the stored map.
+    pub fn sorted_entries(&self) -> Option<Vec<(&String, &JsonValue)>> {
+        let mut entries: Vec<_> = self.as_object()?.iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        Some(entries)
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, JsonValue::Null)
+    }
+
+    fn type_mismatch(&self, expected: JsonKind) -> JsonError {
+        JsonError::TypeMismatch {
+            expected,
+            actual: self.kind(),
+        }
+    }
+
+    /// Like `as_str`, but fails with a `TypeMismatch` so it composes with `?`.
+    pub fn expect_str(&self) -> Result<&str, JsonError> {
+        self.as_str().ok_or_else(|| self.type_mismatch(JsonKind::String))
+    }
+
+    pub fn expect_f64(&self) -> Result<f64, JsonError> {
+        self.as_f64().ok_or_else(|| self.type_mismatch(JsonKind::Number))
+    }
+
+    pub fn expect_bool(&self) -> Result<bool, JsonError> {
+        self.as_bool().ok_or_else(|| self.type_mismatch(JsonKind::Boolean))
+    }
+
+    pub fn expect_null(&self) -> Result<(), JsonError> {
+        if self.is_null() { Ok(()) } else { Err(self.type_mismatch(JsonKind::Null)) }
+    }
+
+    pub fn expect_array(&self) -> Result<&Vec<JsonValue>, JsonError> {
+        self.as_array().ok_or_else(|| self.type_mismatch(JsonKind::Array))
+    }
+
+    pub fn expect_object(&self) -> Result<&Map<String, JsonValue>, JsonError> {
+        self.as_object().ok_or_else(|| self.type_mismatch(JsonKind::Object))
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        match self {
+            JsonValue::Object(map) => map.contains_key(key),
+            _ => false,
+        }
+    }
+
+    /// Looks up an object member by key, ignoring case, for when normalizing keys at parse time
+    /// isn't wanted.
+    ///
+    /// An exact match wins. Otherwise, if several keys match ignoring case, such as `City` and
+    /// `CITY`, the smallest by byte order is used so the result doesn't depend on map order.
+    pub fn get_ci(&self, key: &str) -> Option<&JsonValue> {
+        let map = self.as_object()?;
+        if let Some(value) = map.get(key) {
+            return Some(value);
+        }
+        let key = key.to_lowercase();
+        map.iter()
+            .filter(|(k, _)| k.to_lowercase() == key)
+            .min_by(|a, b| a.0.cmp(b.0))
+            .map(|(_, v)| v)
+    }
+
+    pub fn array_contains(&self, value: &JsonValue) -> bool {
+        match self {
+            JsonValue::Array(arr) => arr.contains(value),
+            _ => false,
+        }
+    }
+
+    /// The first element of an array matching `pred`; `None` for non-arrays or no match.
+    pub fn array_find(&self, pred: impl Fn(&JsonValue) -> bool) -> Option<&JsonValue> {
+        self.as_array()?.iter().find(|v| pred(v))
+    }
+
+    /// Structural equality that treats arrays as unordered multisets: each element must match
+    /// a distinct element of the other array, at any position. Applies at every depth.
+    pub fn multiset_eq(&self, other: &JsonValue) -> bool {
+        match (self, other) {
+            (JsonValue::Array(a), JsonValue::Array(b)) => {
+                if a.len() != b.len() {
+                    return false;
+                }
+                let mut unmatched: Vec<&JsonValue> = b.iter().collect();
+                a.iter().all(|x| match unmatched.iter().position(|y| x.multiset_eq(y)) {
+                    Some(i) => {
+                        unmatched.swap_remove(i);
+                        true
+                    }
+                    None => false,
+                })
+            }
+            (JsonValue::Object(a), JsonValue::Object(b)) => {
+                a.len() == b.len() && a.iter().all(|(k, x)| b.get(k).is_some_and(|y| x.multiset_eq(y)))
+            }
+            _ => self == other,
+        }
+    }
+
+    /// Checks that an object has every key in `required`, failing with `JsonError::MissingKeys`
+    /// listing the ones it lacks; the counterpart of `reject_unknown_keys`. Non-objects fail
+    /// with `JsonError::TypeMismatch`.
+    pub fn require_keys(&self, required: &[&str]) -> Result<(), JsonError> {
+        let map = self.as_object().ok_or_else(|| self.type_mismatch(JsonKind::Object))?;
+        let missing: Vec<String> =
+            required.iter().filter(|k| !map.contains_key(**k)).map(|k| k.to_string()).collect();
+        if missing.is_empty() { Ok(()) } else { Err(JsonError::MissingKeys(missing)) }
+    }
+
+    /// Checks that an object has no keys outside `allowed`, see `reject_unknown_keys_with`.
+    pub fn reject_unknown_keys(&self, allowed: &[&str]) -> Result<(), JsonError> {
+        self.reject_unknown_keys_with(allowed, false)
+    }
+
+    /// Fails with `JsonError::UnknownKey` on the first key, in sorted order, that isn't in
+    /// `allowed`. Only the top-level object is checked unless `recursive` is set, in which case
+    /// objects nested anywhere below it are held to the same list. Non-objects always pass.
+    pub fn reject_unknown_keys_with(&self, allowed: &[&str], recursive: bool) -> Result<(), JsonError> {
+        self.check_keys(allowed, recursive, "")
+    }
+
+    fn check_keys(&self, allowed: &[&str], recursive: bool, pointer: &str) -> Result<(), JsonError> {
+        match self {
+            JsonValue::Object(_) => {
+                for (k, v) in self.sorted_entries().unwrap_or_default() {
+                    let pointer = format!("{}/{}", pointer, crate::pointer::escape(k));
+                    if !allowed.contains(&k.as_str()) {
+                        return Err(JsonError::UnknownKey(pointer));
+                    }
+                    if recursive {
+                        v.check_keys(allowed, recursive, &pointer)?;
+                    }
+                }
+            }
+            JsonValue::Array(arr) if recursive => {
+                for (i, v) in arr.iter().enumerate() {
+                    v.check_keys(allowed, recursive, &format!("{}/{}", pointer, i))?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Fails with `JsonError::OutOfRange` on the first number, including `RawNumber`s, outside
+    /// `min..=max`. Arrays are checked in order and objects in sorted key order, so the same
+    /// document always reports the same pointer.
+    pub fn validate_number_range(&self, min: f64, max: f64) -> Result<(), JsonError> {
+        self.check_range(min..=max, "")
+    }
+
+    fn check_range(&self, range: RangeInclusive<f64>, pointer: &str) -> Result<(), JsonError> {
+        match self {
+            JsonValue::Number(_) | JsonValue::Integer(_) | JsonValue::RawNumber(_) if !self.as_f64().is_some_and(|n| range.contains(&n)) => {
+                return Err(JsonError::OutOfRange(pointer.to_string()));
+            }
+            JsonValue::Array(arr) => {
+                for (i, v) in arr.iter().enumerate() {
+                    v.check_range(range.clone(), &format!("{}/{}", pointer, i))?;
+                }
+            }
+            JsonValue::Object(_) => {
+                for (k, v) in self.sorted_entries().unwrap_or_default() {
+                    v.check_range(range.clone(), &format!("{}/{}", pointer, crate::pointer::escape(k)))?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Keeps only the object entries for which `f` returns `true`; does nothing to non-objects.
+    pub fn retain_object(&mut self, mut f: impl FnMut(&str, &JsonValue) -> bool) {
+        if let JsonValue::Object(map) = self {
+            map.retain(|k, v| f(k, v));
+        }
+    }
+
+    /// Keeps only the array elements for which `f` returns `true`, preserving their order; does
+    /// nothing to non-arrays.
+    pub fn retain_array(&mut self, f: impl FnMut(&JsonValue) -> bool) {
+        if let JsonValue::Array(arr) = self {
+            arr.retain(f);
+        }
+    }
+
+    /// Consumes an array, yielding its elements by value.
+    pub fn into_array_iter(mut self) -> Option<impl Iterator<Item = JsonValue>> {
+        match &mut self {
+            JsonValue::Array(arr) => Some(core::mem::take(arr).into_iter()),
+            _ => None,
+        }
+    }
+
+    /// Consumes an object, yielding its entries by value in no particular order.
+    pub fn into_object_iter(mut self) -> Option<impl Iterator<Item = (String, JsonValue)>> {
+        match &mut self {
+            JsonValue::Object(map) => Some(core::mem::take(map).into_iter()),
+            _ => None,
+        }
+    }
+
+    /// Recursively releases excess capacity left over from parsing.
+    pub fn shrink_to_fit(&mut self) {
+        match self {
+            JsonValue::String(s) | JsonValue::RawNumber(s) => s.shrink_to_fit(),
+            JsonValue::Array(arr) => {
+                arr.iter_mut().for_each(JsonValue::shrink_to_fit);
+                arr.shrink_to_fit();
+            }
+            JsonValue::Object(map) => {
+                map.values_mut().for_each(JsonValue::shrink_to_fit);
+                map.shrink_to_fit();
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::sample;
+
+    #[test]
+    fn expect_should_work() {
+        let json = sample();
+        assert_eq!(json.expect_object().unwrap().len(), 9);
+        assert_eq!(json.pointer("/marks").unwrap().expect_array().unwrap().len(), 3);
+        assert_eq!(json.pointer("/name").unwrap().expect_str(), Ok("John Doe"));
+        assert_eq!(json.pointer("/age").unwrap().expect_f64(), Ok(30.0));
+        assert_eq!(json.pointer("/is_student").unwrap().expect_bool(), Ok(false));
+        assert_eq!(json.pointer("/nested/different_element_array/1").unwrap().expect_null(), Ok(()));
+    }
+
+    #[test]
+    fn expect_should_report_kinds() {
+        let json = sample();
+        let err = json.pointer("/marks").unwrap().expect_object().unwrap_err();
+        assert_eq!(err, JsonError::TypeMismatch { expected: JsonKind::Object, actual: JsonKind::Array });
+        assert_eq!(err.to_string(), "expected object, found array");
+
+        let err = json.pointer("/age").unwrap().expect_str().unwrap_err();
+        assert_eq!(err.to_string(), "expected string, found number");
+    }
+
+    #[test]
+    fn sorted_entries_should_work() {
+        let json = sample();
+        let keys: Vec<&str> = json.sorted_entries().unwrap().into_iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(keys, ["address", "age", "is_student", "marks", "name", "nested", "scientific_number", "scientific_number2", "small_number"]);
+
+        let entries = json.pointer("/address").unwrap().sorted_entries().unwrap();
+        assert_eq!(entries, [(&"city".to_string(), &JsonValue::String("New York".to_string())), (&"zip".to_string(), &JsonValue::Number(10001.0))]);
+        assert_eq!(JsonValue::Null.sorted_entries(), None);
+    }
+
+    #[test]
+    fn contains_key_should_work() {
+        let json = sample();
+        assert!(json.contains_key("address"));
+        assert!(!json.contains_key("city"));
+        assert!(!JsonValue::Null.contains_key("address"));
+    }
+
+    #[test]
+    fn as_i64_should_work() {
+        assert_eq!(JsonValue::Integer(i64::MAX).as_i64(), Some(i64::MAX));
+        assert_eq!(JsonValue::Number(-3.0).as_i64(), Some(-3));
+        assert_eq!(JsonValue::Number(2.5).as_i64(), None);
+        assert_eq!(JsonValue::Number(1e20).as_i64(), None);
+        assert_eq!(JsonValue::RawNumber("9007199254740993".to_string()).as_i64(), Some(9007199254740993));
+        assert_eq!(JsonValue::RawNumber("4e1".to_string()).as_i64(), Some(40));
+        assert_eq!(JsonValue::String("1".to_string()).as_i64(), None);
+    }
+
+    #[test]
+    fn as_bool_loose_should_work() {
+        let string = |s: &str| JsonValue::String(s.to_string());
+        for (s, expected) in [("true", true), ("yes", true), ("1", true), ("false", false), ("no", false), ("0", false)] {
+            assert_eq!(string(s).as_bool_loose(), Some(expected), "{}", s);
+        }
+        for s in ["TRUE", " yes", "on", "2", ""] {
+            assert_eq!(string(s).as_bool_loose(), None, "{}", s);
+        }
+
+        assert_eq!(JsonValue::Number(1.0).as_bool_loose(), Some(true));
+        assert_eq!(JsonValue::Number(0.0).as_bool_loose(), Some(false));
+        assert_eq!(JsonValue::RawNumber("1.0".to_string()).as_bool_loose(), Some(true));
+        assert_eq!(JsonValue::Number(0.5).as_bool_loose(), None);
+
+        assert_eq!(JsonValue::Boolean(true).as_bool_loose(), Some(true));
+        assert_eq!(JsonValue::Boolean(false).as_bool_loose(), Some(false));
+        assert_eq!(JsonValue::Null.as_bool_loose(), None);
+    }
+
+    #[test]
+    fn get_ci_should_work() {
+        let json = sample();
+        let address = json.pointer("/address").unwrap();
+        assert_eq!(address.get_ci("City"), Some(&JsonValue::String("New York".to_string())));
+        assert_eq!(json.get_ci("NAME").and_then(JsonValue::as_str), Some("John Doe"));
+        assert_eq!(json.get_ci("missing"), None);
+        assert_eq!(JsonValue::Null.get_ci("city"), None);
+
+        let json = crate::parse_json(&mut r#"{"CITY": 1, "City": 2, "city": 3}"#).unwrap();
+        assert_eq!(json.get_ci("city"), Some(&JsonValue::Number(3.0)));
+        assert_eq!(json.get_ci("cItY"), Some(&JsonValue::Number(1.0)));
+    }
+
+    #[test]
+    fn array_contains_should_work() {
+        let json = sample();
+        let marks = json.pointer("/marks").unwrap();
+        assert!(marks.array_contains(&JsonValue::Number(-80.0)));
+        assert!(!marks.array_contains(&JsonValue::Number(80.0)));
+        assert!(!json.array_contains(&JsonValue::Number(-80.0)));
+    }
+
+    #[test]
+    fn array_find_should_work() {
+        let json = sample();
+        let mixed = json.pointer("/nested/different_element_array").unwrap();
+        let first_object = mixed.array_find(|v| v.as_object().is_some());
+        assert_eq!(first_object, json.pointer("/nested/different_element_array/4"));
+        assert_eq!(mixed.array_find(JsonValue::is_null), Some(&JsonValue::Null));
+
+        assert_eq!(mixed.array_find(|v| v.as_array().is_some()), None);
+        assert_eq!(json.array_find(|_| true), None);
+    }
+
+    #[test]
+    fn multiset_eq_should_work() {
+        let json = |input: &str| crate::parse_json(&mut (&*input)).unwrap();
+
+        let (a, b) = (json("[1, 2, 3]"), json("[3, 2, 1]"));
+        assert_ne!(a, b);
+        assert!(a.multiset_eq(&b));
+
+        assert!(json(r#"{"x": [[1, 2], {"y": [true, null]}]}"#).multiset_eq(&json(r#"{"x": [{"y": [null, true]}, [2, 1]]}"#)));
+        assert!(json("[1, 1, 2]").multiset_eq(&json("[1, 2, 1]")));
+        assert!(!json("[1, 1, 2]").multiset_eq(&json("[1, 2, 2]")));
+        assert!(!json("[1, 2]").multiset_eq(&json("[1, 2, 2]")));
+        assert!(!json(r#"{"a": 1}"#).multiset_eq(&json(r#"{"b": 1}"#)));
+    }
+
+    #[test]
+    fn require_keys_should_work() {
+        let json = crate::parse_json(&mut r#"{"name": "x", "port": 80}"#).unwrap();
+        assert_eq!(json.require_keys(&["name", "port"]), Ok(()));
+        let err = json.require_keys(&["name", "host", "port"]).unwrap_err();
+        assert_eq!(err, JsonError::MissingKeys(vec!["host".to_string()]));
+        assert_eq!(err.to_string(), "missing keys `host`");
+        assert_eq!(json.require_keys(&["b", "a"]).unwrap_err().to_string(), "missing keys `b`, `a`");
+        assert!(matches!(JsonValue::Null.require_keys(&[]), Err(JsonError::TypeMismatch { .. })));
+    }
+
+    #[test]
+    fn reject_unknown_keys_should_work() {
+        let json = crate::parse_json(&mut r#"{"name": "x", "port": 80, "debgu": true, "tls": {"cert": ""}}"#).unwrap();
+        let err = json.reject_unknown_keys(&["name", "port", "debug", "tls"]).unwrap_err();
+        assert_eq!(err, JsonError::UnknownKey("/debgu".to_string()));
+        assert_eq!(err.to_string(), "unknown key at `/debgu`");
+
+        let allowed = ["name", "port", "debgu", "tls"];
+        assert_eq!(json.reject_unknown_keys(&allowed), Ok(()));
+        let err = json.reject_unknown_keys_with(&allowed, true).unwrap_err();
+        assert_eq!(err.pointer(), Some("/tls/cert"));
+        assert_eq!(JsonValue::Null.reject_unknown_keys(&[]), Ok(()));
+    }
+
+    #[test]
+    fn validate_number_range_should_work() {
+        let json = crate::parse_json(&mut r#"{"id": "s1", "readings": [20.5, -3, 120, 19], "max": 99}"#).unwrap();
+        assert_eq!(json.validate_number_range(-10.0, 100.0), Err(JsonError::OutOfRange("/readings/2".to_string())));
+        assert_eq!(json.validate_number_range(-10.0, 50.0).unwrap_err().pointer(), Some("/max"));
+        assert_eq!(json.validate_number_range(-3.0, 120.0), Ok(()));
+
+        assert!(sample().validate_number_range(-100.0, 100.0).is_err());
+        assert_eq!(JsonValue::String("1e9".to_string()).validate_number_range(0.0, 1.0), Ok(()));
+    }
+
+    #[test]
+    fn retain_object_should_work() {
+        let mut json = sample();
+        json.retain_object(|_, v| v.as_f64().is_some());
+        let mut keys: Vec<&String> = json.as_object().unwrap().keys().collect();
+        keys.sort();
+        assert_eq!(keys, ["age", "scientific_number", "scientific_number2", "small_number"]);
+
+        let mut age = json.extract("/age").unwrap();
+        age.retain_object(|_, _| false);
+        assert_eq!(age, JsonValue::Number(30.0));
+    }
+
+    #[test]
+    fn retain_array_should_work() {
+        let mut json = crate::parse_json(&mut "[3, -1, 0, 2, \"4\", -5]").unwrap();
+        json.retain_array(|v| v.as_f64().is_some_and(|n| n > 0.0));
+        assert_eq!(json, JsonValue::Array(vec![JsonValue::Number(3.0), JsonValue::Number(2.0)]));
+
+        let mut json = sample();
+        json.retain_array(|_| false);
+        assert_eq!(json, sample());
+    }
+
+    #[test]
+    fn shrink_to_fit_should_work() {
+        let input = format!("[{}]", vec!["[1, 2, 3]"; 1000].join(","));
+        let mut json = crate::parse_json(&mut input.as_str()).unwrap();
+        json.shrink_to_fit();
+
+        let JsonValue::Array(arr) = &json else { panic!("expected array") };
+        assert_eq!(arr.capacity(), arr.len());
+        let JsonValue::Array(inner) = &arr[0] else { panic!("expected array") };
+        assert_eq!(inner.capacity(), inner.len());
+    }
+
+    #[test]
+    fn into_array_iter_should_work() {
+        let json = sample();
+        let marks = json.pointer("/marks").unwrap().clone();
+        let ret: Vec<JsonValue> = marks.clone().into_array_iter().unwrap().collect();
+        assert_eq!(JsonValue::Array(ret), marks);
+        assert!(json.into_array_iter().is_none());
+    }
+
+    #[test]
+    fn into_object_iter_should_work() {
+        let json = sample();
+        let address = json.pointer("/address").unwrap().clone();
+        let ret: Map<String, JsonValue> = address.clone().into_object_iter().unwrap().collect();
+        assert_eq!(JsonValue::Object(ret), address);
+        assert!(JsonValue::Null.into_object_iter().is_none());
+    }
+}
@@ -0,0 +1,106 @@
+use core::cell::RefCell;
+use core::fmt::{self, Display, Formatter};
+use anyhow::Result;
+use winnow::Parser;
+use winnow::ascii::multispace0;
+use winnow::combinator::{delimited, eof, terminated};
+use winnow::error::{StrContext, StrContextValue};
+use crate::error::line_column;
+use crate::options::ParseState;
+use crate::{expect_value, syntax_error, Input, JsonValue, ParserOptions};
+use crate::prelude::*;
+
+/// Something `parse_json_with_warnings` tolerated instead of rejecting or silently accepting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WarningKind {
+    /// The key appeared earlier in the same object; the later value wins.
+    DuplicateKey(String),
+    /// A `,` directly before a closing `]` or `}` was ignored.
+    TrailingComma,
+    /// The number literal has redundant leading zeros, like `007`.
+    LeadingZeros(String),
+}
+
+impl Display for WarningKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            WarningKind::DuplicateKey(key) => write!(f, "duplicate key `{}`", key),
+            WarningKind::TrailingComma => f.write_str("trailing comma"),
+            WarningKind::LeadingZeros(literal) => write!(f, "leading zeros in `{}`", literal),
+        }
+    }
+}
+
+/// A located `WarningKind`; `offset` is in bytes, `line` and `column` are 1-based.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    pub kind: WarningKind,
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: {}", self.line, self.column, self.kind)
+    }
+}
+
+/// Parses like `parse_json`, but tolerates trailing commas and reports them, along with
+/// duplicate keys and leading zeros, as warnings in source order.
+pub fn parse_json_with_warnings(input: &str) -> Result<(JsonValue, Vec<Warning>)> {
+    let options = ParserOptions::default();
+    let mut state = ParseState::new(&options);
+    state.warnings = Some(RefCell::new(Vec::new()));
+    let mut stateful = Input { input, state: &state };
+
+    let end = eof.context(StrContext::Expected(StrContextValue::Description("end of input")));
+    let value = terminated(delimited(multispace0, expect_value, multispace0), end)
+        .parse_next(&mut stateful)
+        .map_err(|e| syntax_error(input, &stateful, e))?;
+
+    let warnings = state.warnings.take().unwrap_or_default().into_inner()
+        .into_iter()
+        .map(|(remaining, kind)| {
+            let offset = input.len() - remaining;
+            let (line, column) = line_column(input, offset);
+            Warning { kind, offset, line, column }
+        })
+        .collect();
+    Ok((value, warnings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_json;
+
+    #[test]
+    fn parse_json_with_warnings_should_work() {
+        let input = "{\"a\": 1, \"b\": [1, 2,],\n \"a\": 3}";
+        let (value, warnings) = parse_json_with_warnings(input).unwrap();
+        assert_eq!(value, parse_json(&mut r#"{"a": 3, "b": [1, 2]}"#).unwrap());
+        assert_eq!(warnings, vec![
+            Warning { kind: WarningKind::TrailingComma, offset: 19, line: 1, column: 20 },
+            Warning { kind: WarningKind::DuplicateKey("a".to_string()), offset: 24, line: 2, column: 2 },
+        ]);
+        assert_eq!(warnings[1].to_string(), "line 2, column 2: duplicate key `a`");
+        assert!(parse_json(&mut (&*input)).is_err());
+    }
+
+    #[test]
+    fn parse_json_with_warnings_should_report_leading_zeros() {
+        let (value, warnings) = parse_json_with_warnings(r#"{"x": [007, -01.5, 0, 0.5], "y": {"z": 1,},}"#).unwrap();
+        assert_eq!(value.pointer("/x/0"), Some(&JsonValue::Number(7.0)));
+        let kinds: Vec<WarningKind> = warnings.into_iter().map(|w| w.kind).collect();
+        assert_eq!(kinds, [
+            WarningKind::LeadingZeros("007".to_string()),
+            WarningKind::LeadingZeros("-01.5".to_string()),
+            WarningKind::TrailingComma,
+            WarningKind::TrailingComma,
+        ]);
+
+        assert_eq!(parse_json_with_warnings("[1, 2]").unwrap().1, []);
+        assert!(parse_json_with_warnings("[1, 2,,]").is_err());
+    }
+}
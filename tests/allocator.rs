@@ -0,0 +1,56 @@
+//! Parsing under an installed `#[global_allocator]`, the hook `no_std` builds rely on.
+#![cfg(feature = "std")]
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use rs_json_parser::{parse_json, JsonValue, Map};
+
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    static LIVE_BYTES: Cell<isize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|n| n.set(n.get() + 1));
+        LIVE_BYTES.with(|n| n.set(n.get() + layout.size() as isize));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE_BYTES.with(|n| n.set(n.get() - layout.size() as isize));
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+#[test]
+fn parse_should_allocate_through_global_allocator() {
+    let input = r#"{"name": "John Doe", "tags": ["alpha", "beta"], "age": 30, "ok": true, "none": null}"#;
+    let mut expected = Map::new();
+    expected.insert("name".to_string(), JsonValue::String("John Doe".to_string()));
+    expected.insert("tags".to_string(), JsonValue::Array(vec![
+        JsonValue::String("alpha".to_string()),
+        JsonValue::String("beta".to_string()),
+    ]));
+    expected.insert("age".to_string(), JsonValue::Number(30.0));
+    expected.insert("ok".to_string(), JsonValue::Boolean(true));
+    expected.insert("none".to_string(), JsonValue::Null);
+    let expected = JsonValue::Object(expected);
+
+    let allocations = ALLOCATIONS.with(Cell::get);
+    let live = LIVE_BYTES.with(Cell::get);
+    let json = parse_json(&mut &input[..]).unwrap();
+    assert!(ALLOCATIONS.with(Cell::get) > allocations);
+    assert!(LIVE_BYTES.with(Cell::get) > live);
+    assert_eq!(json, expected);
+    assert_eq!(json.to_string().len(), input.replace(": ", ":").replace(", ", ",").len());
+
+    // Everything the parse kept alive goes back through the same allocator.
+    drop(json);
+    assert_eq!(LIVE_BYTES.with(Cell::get), live);
+}